@@ -57,12 +57,11 @@ impl Texture {
 
 	pub fn from_image(device: &Device, layout: &BindGroupLayout, queue: &Queue, image: &Image) -> Texture {
 		let frame = image.get_frame(0);
-		let buffer = frame.to_raw(None);
-		let width = frame.header().width() as u32;
-		let height = frame.header().height() as u32;
+		let buffer = frame.to_rgba_image(None);
+		let (width, height) = buffer.dimensions();
 		let result = Self::new(device, layout, width, height);
 
-		result.write(queue, &buffer, Origin3d::ZERO, width, height);
+		result.write(queue, buffer.as_raw(), Origin3d::ZERO, width, height);
 		result
 	}
 
@@ -2,7 +2,6 @@ use cgmath::Vector2;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
-use tim2::Pixel;
 use wgpu::*;
 
 use crate::common::*;
@@ -415,15 +414,14 @@ impl Tilemap {
 }
 
 fn load_frame<P: AsRef<Path>>(path: P, name: &str, kind: CellKind) -> Result<Vec<u8>> {
-	let color_key = Pixel::from(0, 255, 0, 255);
 	let filename = format!("{}_{}.tm2", name, kind.get_suffix());
 	let path = path.as_ref().join(filename);
 
 	let image = tim2::load(path)?;
 	let frame = image.get_frame(0);
-	let pixels = frame.to_raw(Some(color_key));
+	let pixels = frame.to_rgba_image(Some(tim2::COLOR_KEY));
 
-	Ok(pixels)
+	Ok(pixels.into_raw())
 }
 
 fn build_texture<P: AsRef<Path>>(core: &GraphicsCore, path: P, name: &str) -> Result<Texture> {
@@ -0,0 +1,236 @@
+use crate::error::{Error, Result};
+use crate::input::Input;
+
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+type Hook = Arc<dyn Fn(&PanicHookInfo<'_>) + Sync + Send>;
+
+thread_local! {
+	/// Message of the last panic while converting a file, `None` outside of a conversion.
+	static PANIC_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Puts back the panic hook `run` replaces when dropped, so it is restored however `run` ends.
+struct RestoreHook(Hook);
+
+impl Drop for RestoreHook {
+	fn drop(&mut self) {
+		// hooks can't be changed while unwinding, but the replacement forwards to this one then
+		if !thread::panicking() {
+			let hook = self.0.clone();
+
+			panic::set_hook(Box::new(move |info| hook(info)));
+		}
+	}
+}
+
+/// A file that failed to convert.
+#[derive(Debug)]
+pub struct Failure {
+	pub path: PathBuf,
+	pub kind: String,
+	pub message: String,
+}
+
+/// Runs `f` on a file, catching its errors and panics.
+fn convert<F>(input: &Input, f: &F) -> Option<Failure>
+where
+	F: Fn(&Input) -> Result<()>,
+{
+	PANIC_MESSAGE.with(|v| *v.borrow_mut() = Some(String::new()));
+
+	let result = panic::catch_unwind(AssertUnwindSafe(|| f(input)));
+	let message = PANIC_MESSAGE.with(|v| v.take()).unwrap_or_default();
+	let (kind, message) = match result {
+		Ok(Ok(_)) => return None,
+		Ok(Err(err)) => (err.kind(), err.to_string()),
+		Err(_) => ("Panic".to_string(), message.replace('\n', " ")),
+	};
+
+	Some(Failure {
+		path: input.path.clone(),
+		kind,
+		message,
+	})
+}
+
+/// Fails every input whose outputs would go where those of an earlier input go, since the
+/// two would be written at the same time. Returns the failures and the inputs left.
+fn find_same_outputs<'a>(inputs: &'a [Input], output: Option<&Path>) -> (Vec<Failure>, Vec<&'a Input>) {
+	let mut seen: HashMap<PathBuf, &Path> = HashMap::new();
+	let mut failures = Vec::new();
+	let mut result = Vec::new();
+
+	for input in inputs {
+		match seen.get(&input.output_stem(output)) {
+			Some(first) => {
+				let err = Error::SameOutput(first.to_path_buf());
+
+				failures.push(Failure {
+					path: input.path.clone(),
+					kind: err.kind(),
+					message: err.to_string(),
+				});
+			},
+			None => {
+				seen.insert(input.output_stem(output), &input.path);
+				result.push(input);
+			},
+		}
+	}
+
+	(failures, result)
+}
+
+/// Runs `f` on every input in parallel, on `jobs` threads or one per core when it is 0.
+/// Errors and panics are caught per file and returned instead of stopping the batch, as
+/// are inputs whose outputs would go where those of an earlier input in `output` go.
+pub fn run<F>(inputs: &[Input], output: Option<&Path>, jobs: usize, f: F) -> Result<Vec<Failure>>
+where
+	F: Fn(&Input) -> Result<()> + Sync,
+{
+	let pool = rayon::ThreadPoolBuilder::new()
+		.num_threads(jobs)
+		.build()
+		.map_err(io::Error::other)?;
+
+	// keeps the message and location of panics in conversions for the summary instead of
+	// printing them, and leaves any other panic to the previous hook
+	let hook: Hook = panic::take_hook().into();
+	let _restore = RestoreHook(hook.clone());
+
+	panic::set_hook(Box::new(move |info| {
+		let captured = PANIC_MESSAGE.with(|v| v.borrow_mut().as_mut().map(|v| *v = info.to_string()).is_some());
+
+		if !captured {
+			hook(info);
+		}
+	}));
+
+	let (mut result, inputs) = find_same_outputs(inputs, output);
+
+	result.extend(pool.install(|| inputs.par_iter().filter_map(|input| convert(input, &f)).collect::<Vec<_>>()));
+
+	result.sort_by(|a, b| a.path.cmp(&b.path));
+
+	Ok(result)
+}
+
+/// Writes a Markdown summary of a batch, listing its failures grouped by kind.
+pub fn summarize(title: &str, total: usize, failures: &[Failure]) -> String {
+	let mut groups: BTreeMap<&str, Vec<&Failure>> = BTreeMap::new();
+	let mut result = format!("# {}\n\n", title);
+
+	for failure in failures {
+		groups.entry(&failure.kind).or_default().push(failure);
+	}
+
+	let _ = writeln!(result, "{} of {} files converted, {} failed.", total - failures.len(), total, failures.len());
+
+	for (kind, failures) in groups {
+		let _ = write!(result, "\n## {} ({})\n\n", kind, failures.len());
+
+		for failure in failures {
+			let _ = writeln!(result, "- {}: {}", failure.path.display(), failure.message);
+		}
+	}
+
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::Mutex;
+
+	static PREVIOUS_HOOK_RAN: AtomicBool = AtomicBool::new(false);
+
+	#[test]
+	fn catches_failures_and_restores_the_panic_hook() {
+		let inputs: Vec<Input> = ["c", "a", "b"].iter().map(|v| Input { path: v.into(), dir: PathBuf::new() }).collect();
+
+		panic::set_hook(Box::new(|_| PREVIOUS_HOOK_RAN.store(true, Ordering::SeqCst)));
+
+		let failures = run(&inputs, None, 2, |input| match input.path.to_str() {
+			Some("a") => panic!("broken a"),
+			Some("b") => Err(Error::NoFrame(3)),
+			_ => Ok(()),
+		})
+		.unwrap();
+
+		assert_eq!(failures.len(), 2);
+		assert_eq!((failures[0].path.as_path(), failures[0].kind.as_str()), (Path::new("a"), "Panic"));
+		assert!(failures[0].message.contains("broken a"));
+		assert_eq!((failures[1].path.as_path(), failures[1].kind.as_str()), (Path::new("b"), "NoFrame"));
+		assert!(!PREVIOUS_HOOK_RAN.load(Ordering::SeqCst));
+
+		let _ = panic::catch_unwind(|| panic!("after the batch"));
+		let _ = panic::take_hook();
+
+		assert!(PREVIOUS_HOOK_RAN.load(Ordering::SeqCst));
+	}
+
+	#[test]
+	fn summarizes_failures_by_kind() {
+		let failure = |path: &str, kind: &str, message: &str| Failure {
+			path: PathBuf::from(path),
+			kind: kind.to_string(),
+			message: message.to_string(),
+		};
+
+		let failures = [
+			failure("b.tm2", "UnexpectedEof", "unexpected end of data"),
+			failure("a.tm2", "Panic", "boom"),
+			failure("c.tm2", "UnexpectedEof", "unexpected end of data"),
+		];
+
+		let expected = "# TM2 to PNG\n\n\
+			7 of 10 files converted, 3 failed.\n\
+			\n## Panic (1)\n\n\
+			- a.tm2: boom\n\
+			\n## UnexpectedEof (2)\n\n\
+			- b.tm2: unexpected end of data\n\
+			- c.tm2: unexpected end of data\n";
+
+		assert_eq!(summarize("TM2 to PNG", 10, &failures), expected);
+		assert_eq!(summarize("Empty", 0, &[]), "# Empty\n\n0 of 0 files converted, 0 failed.\n");
+	}
+
+	#[test]
+	fn fails_inputs_with_the_same_outputs() {
+		let input = |path: &str| Input { path: path.into(), dir: PathBuf::new() };
+		let inputs = [input("a/x.tm2"), input("b/x.tm2"), input("b/x.TM2"), input("b/y.tm2")];
+		let converted = Mutex::new(Vec::new());
+		let convert = |input: &Input| {
+			converted.lock().unwrap().push(input.path.clone());
+			Ok(())
+		};
+
+		let failures = run(&inputs, None, 2, convert).unwrap();
+
+		assert_eq!(converted.lock().unwrap().len(), 3);
+		assert_eq!(failures.len(), 1);
+		assert_eq!(failures[0].message, "would write the same files as b/x.tm2");
+
+		converted.lock().unwrap().clear();
+
+		let failures = run(&inputs, Some(Path::new("out")), 2, convert).unwrap();
+		let mut converted = converted.into_inner().unwrap();
+
+		converted.sort();
+		assert_eq!(converted, [PathBuf::from("a/x.tm2"), PathBuf::from("b/y.tm2")]);
+		assert_eq!(failures.len(), 2);
+		assert_eq!((failures[0].path.as_path(), failures[0].kind.as_str()), (Path::new("b/x.TM2"), "SameOutput"));
+		assert_eq!(failures[0].message, "would write the same files as a/x.tm2");
+		assert_eq!(failures[1].path, Path::new("b/x.tm2"));
+	}
+}
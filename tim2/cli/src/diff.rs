@@ -0,0 +1,45 @@
+use crate::error::Result;
+use crate::input::{frame_stem, output_path};
+
+use std::path::Path;
+
+/// Compares the frames of two files, writing a diff PNG per frame into `output` when given.
+/// Returns true when every frame matches.
+pub fn diff(left: &Path, right: &Path, output: Option<&Path>) -> Result<bool> {
+	let left_image = tim2::load(left)?;
+	let right_image = tim2::load(right)?;
+	let options = tim2::CompareOptions { diff_image: output.is_some() };
+	let count = left_image.frames().len();
+	let mut identical = count == right_image.frames().len();
+
+	if !identical {
+		println!("frame count: {} != {}", count, right_image.frames().len());
+	}
+
+	for (i, (l, r)) in left_image.frames().iter().zip(right_image.frames()).enumerate() {
+		let diff = tim2::compare_with_options(l, r, options);
+
+		for field in &diff.header {
+			println!("frame[{}] {}: {:#x} != {:#x}", i, field.name, field.left, field.right);
+		}
+
+		if diff.user_data_differs {
+			println!("frame[{}] user data differs", i);
+		}
+
+		if !diff.palettes.is_empty() {
+			println!("frame[{}] palette entries differing: {}", i, diff.palettes.len());
+		}
+
+		println!("frame[{}] mismatched pixels: {}, PSNR: {:.2} dB", i, diff.mismatched_pixels, diff.psnr);
+		identical &= diff.is_identical();
+
+		if let Some(raw) = diff.diff_image {
+			let path = output_path(left, output, &format!("{}_diff.png", frame_stem(left, i, count)));
+
+			image::RgbaImage::from_raw(diff.width, diff.height, raw).unwrap().save(path)?;
+		}
+	}
+
+	Ok(identical)
+}
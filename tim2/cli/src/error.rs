@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::result;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	Image(image::ImageError),
+	Png(png::EncodingError),
+	Tim2(tim2::Error),
+	Pattern(glob::PatternError),
+	NoMatch(String),
+	NoFrame(usize),
+	WouldOverwrite(PathBuf),
+	SameOutput(PathBuf),
+}
+
+impl Error {
+	/// Name of the kind of error, used to group failures.
+	pub fn kind(&self) -> String {
+		match self {
+			Error::Io(err) | Error::Tim2(tim2::Error::Io(err)) => format!("Io({:?})", err.kind()),
+			Error::Image(_) => "Image".to_string(),
+			Error::Png(_) => "Png".to_string(),
+			Error::Tim2(err) => err.kind().to_string(),
+			Error::Pattern(_) => "Pattern".to_string(),
+			Error::NoMatch(_) => "NoMatch".to_string(),
+			Error::NoFrame(_) => "NoFrame".to_string(),
+			Error::WouldOverwrite(_) => "WouldOverwrite".to_string(),
+			Error::SameOutput(_) => "SameOutput".to_string(),
+		}
+	}
+}
+
+impl Error {
+	/// Whether the error comes from the command line rather than from processing a file.
+	pub fn is_usage(&self) -> bool {
+		matches!(self, Error::Pattern(_) | Error::NoMatch(_))
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Io(err) => write!(f, "{}", err),
+			Error::Image(err) => write!(f, "{}", err),
+			Error::Png(err) => write!(f, "{}", err),
+			Error::Tim2(err) => write!(f, "{}", err),
+			Error::Pattern(err) => write!(f, "invalid pattern: {}", err),
+			Error::NoMatch(input) => write!(f, "no input matches {:?}", input),
+			Error::NoFrame(index) => write!(f, "the template has no frame {}", index),
+			Error::WouldOverwrite(path) => write!(f, "refusing to overwrite {}", path.display()),
+			Error::SameOutput(path) => write!(f, "would write the same files as {}", path.display()),
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Error {
+		Error::Io(err)
+	}
+}
+
+impl From<image::ImageError> for Error {
+	fn from(err: image::ImageError) -> Error {
+		Error::Image(err)
+	}
+}
+
+impl From<png::EncodingError> for Error {
+	fn from(err: png::EncodingError) -> Error {
+		Error::Png(err)
+	}
+}
+
+impl From<tim2::Error> for Error {
+	fn from(err: tim2::Error) -> Error {
+		Error::Tim2(err)
+	}
+}
+
+impl From<glob::PatternError> for Error {
+	fn from(err: glob::PatternError) -> Error {
+		Error::Pattern(err)
+	}
+}
@@ -0,0 +1,314 @@
+use crate::error::Result;
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tim2::{Diagnostic, FrameLayout, Header};
+
+const CSV_COLUMNS: &[&str] = &[
+	"file",
+	"frame",
+	"offset",
+	"width",
+	"height",
+	"bpp",
+	"pixel_format",
+	"storage",
+	"clut_format",
+	"clut_linear",
+	"clut_colors",
+	"palette_count",
+	"mipmap_count",
+	"gs_regs",
+	"gs_tex_0",
+	"gs_tex_1",
+	"gs_tex_clut",
+	"gs_miptbp_1",
+	"gs_miptbp_2",
+	"comment",
+	"diagnostics",
+	"error",
+];
+
+/// Output formats of `info`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum InfoFormat {
+	Text,
+	Json,
+	Csv,
+}
+
+/// Metadata of one file, for the structured output formats.
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+	pub file: String,
+	pub version: Option<u8>,
+	pub align: Option<u8>,
+	pub frames: Vec<FrameReport>,
+	pub diagnostics: Vec<DiagnosticReport>,
+	pub error: Option<String>,
+}
+
+/// Metadata of one frame. GS registers are hex strings, as they don't fit in a JSON number.
+#[derive(Debug, Serialize)]
+pub struct FrameReport {
+	pub index: usize,
+	pub offset: u64,
+	pub width: u16,
+	pub height: u16,
+	pub bpp: u8,
+	pub pixel_format: Option<String>,
+	pub storage: String,
+	pub clut_format: Option<String>,
+	pub clut_linear: Option<bool>,
+	pub clut_colors: u16,
+	pub palette_count: usize,
+	pub mipmap_count: u8,
+	pub gs_regs: String,
+	pub gs_tex_0: String,
+	pub gs_tex_1: String,
+	pub gs_tex_clut: String,
+	pub gs_miptbp_1: String,
+	pub gs_miptbp_2: String,
+	pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticReport {
+	pub severity: String,
+	pub frame: Option<usize>,
+	pub offset: usize,
+	pub kind: String,
+}
+
+impl From<&Diagnostic> for DiagnosticReport {
+	fn from(diagnostic: &Diagnostic) -> DiagnosticReport {
+		DiagnosticReport {
+			severity: format!("{:?}", diagnostic.severity),
+			frame: diagnostic.frame,
+			offset: diagnostic.offset,
+			kind: format!("{:?}", diagnostic.kind),
+		}
+	}
+}
+
+fn palette_count(header: &Header) -> usize {
+	let palette_size = header.clut_color_count() as usize * header.color_size() as usize;
+
+	header.clut_size() as usize / palette_size.max(1)
+}
+
+/// Prints a summary of the headers of a TIM2 file, or all of them when `verbose` is set.
+pub fn info(input: &Path, verbose: bool) -> Result<()> {
+	let buffer = fs::read(input)?;
+	let inspection = tim2::inspect(&buffer)?;
+
+	if verbose {
+		println!("{}: {:#?}", input.display(), inspection);
+		return Ok(());
+	}
+
+	println!("{}: version {}, {} frame(s)", input.display(), inspection.version, inspection.frames.len());
+
+	for (i, frame) in inspection.frames.iter().enumerate() {
+		let header = &frame.header;
+		let format = header.pixel_format().map_or_else(|_| format!("{} bpp", header.bpp()), |v| format!("{:?}", v));
+
+		print!("  frame[{}]: {}x{} {}, {:?}", i, header.width(), header.height(), format, header.storage());
+
+		if header.is_paletted() {
+			print!(", {} palette(s) of {} colors", palette_count(header), header.clut_color_count());
+		}
+
+		if header.has_mipmaps() {
+			print!(", {} levels", header.level_count());
+		}
+
+		println!();
+
+		if let Some(comment) = header.user_data().comment() {
+			println!("  frame[{}] comment: {}", i, comment);
+		}
+	}
+
+	Ok(())
+}
+
+fn clut_format(header: &Header) -> Option<String> {
+	if !header.is_paletted() {
+		return None;
+	}
+
+	let name = match header.clut_format() & 0x07 {
+		1 => "Abgr1555".to_string(),
+		2 => "Rgb888".to_string(),
+		3 => "Rgba8888".to_string(),
+		n => format!("Unknown({})", n),
+	};
+
+	Some(name)
+}
+
+fn frame_report(index: usize, frame: &FrameLayout) -> FrameReport {
+	let header = &frame.header;
+
+	FrameReport {
+		index,
+		offset: frame.offset,
+		width: header.width(),
+		height: header.height(),
+		bpp: header.bpp(),
+		pixel_format: header.pixel_format().ok().map(|v| format!("{:?}", v)),
+		storage: format!("{:?}", header.storage()),
+		clut_format: clut_format(header),
+		clut_linear: header.is_paletted().then(|| header.is_linear_palette()),
+		clut_colors: header.clut_color_count(),
+		palette_count: palette_count(header),
+		mipmap_count: header.mipmap_count(),
+		gs_regs: format!("{:#010x}", header.gs_regs()),
+		gs_tex_0: format!("{:#018x}", header.gs_tex_0()),
+		gs_tex_1: format!("{:#018x}", header.gs_tex_1()),
+		gs_tex_clut: format!("{:#010x}", header.gs_tex_clut()),
+		gs_miptbp_1: format!("{:#018x}", header.gs_miptbp_1()),
+		gs_miptbp_2: format!("{:#018x}", header.gs_miptbp_2()),
+		comment: header.user_data().comment().map(str::to_string),
+	}
+}
+
+/// Gathers the metadata and validation results of a file. Failures to read it end up in
+/// `error` rather than failing the report.
+pub fn report(input: &Path) -> FileReport {
+	let mut result = FileReport {
+		file: input.display().to_string(),
+		version: None,
+		align: None,
+		frames: Vec::new(),
+		diagnostics: Vec::new(),
+		error: None,
+	};
+
+	let buffer = match fs::read(input) {
+		Ok(v) => v,
+		Err(err) => {
+			result.error = Some(err.to_string());
+			return result;
+		},
+	};
+
+	result.diagnostics = tim2::validate(&buffer).iter().map(DiagnosticReport::from).collect();
+
+	match tim2::inspect(&buffer) {
+		Ok(inspection) => {
+			result.version = Some(inspection.version);
+			result.align = Some(inspection.align);
+			result.frames = inspection.frames.iter().enumerate().map(|(i, v)| frame_report(i, v)).collect();
+		},
+		Err(err) => result.error = Some(err.to_string()),
+	}
+
+	result
+}
+
+pub fn print_json(reports: &[FileReport]) {
+	println!("{}", serde_json::to_string_pretty(reports).expect("reports serialize to JSON"));
+}
+
+fn csv_field(value: &str) -> String {
+	if value.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", value.replace('"', "\"\""))
+	} else {
+		value.to_string()
+	}
+}
+
+fn print_csv_row(fields: &[String]) {
+	let fields: Vec<String> = fields.iter().map(|v| csv_field(v)).collect();
+
+	println!("{}", fields.join(","));
+}
+
+pub fn print_csv_header() {
+	println!("{}", CSV_COLUMNS.join(","));
+}
+
+/// Prints one row per frame. Diagnostics of the whole file are repeated on every row, and
+/// a file that can't be read gets a single row holding the error.
+pub fn print_csv(report: &FileReport) {
+	let diagnostics = |frame: Option<usize>| -> String {
+		let diagnostics = report.diagnostics.iter().filter(|v| v.frame.is_none() || v.frame == frame);
+
+		diagnostics.map(|v| format!("{}: {}", v.severity, v.kind)).collect::<Vec<_>>().join("; ")
+	};
+
+	let optional = |v: Option<String>| v.unwrap_or_default();
+
+	if report.frames.is_empty() {
+		let mut fields = vec![String::new(); CSV_COLUMNS.len()];
+
+		fields[0] = report.file.clone();
+		fields[CSV_COLUMNS.len() - 2] = diagnostics(None);
+		fields[CSV_COLUMNS.len() - 1] = optional(report.error.clone());
+		print_csv_row(&fields);
+		return;
+	}
+
+	for frame in &report.frames {
+		print_csv_row(&[
+			report.file.clone(),
+			frame.index.to_string(),
+			frame.offset.to_string(),
+			frame.width.to_string(),
+			frame.height.to_string(),
+			frame.bpp.to_string(),
+			optional(frame.pixel_format.clone()),
+			frame.storage.clone(),
+			optional(frame.clut_format.clone()),
+			optional(frame.clut_linear.map(|v| v.to_string())),
+			frame.clut_colors.to_string(),
+			frame.palette_count.to_string(),
+			frame.mipmap_count.to_string(),
+			frame.gs_regs.clone(),
+			frame.gs_tex_0.clone(),
+			frame.gs_tex_1.clone(),
+			frame.gs_tex_clut.clone(),
+			frame.gs_miptbp_1.clone(),
+			frame.gs_miptbp_2.clone(),
+			optional(frame.comment.clone()),
+			diagnostics(Some(frame.index)),
+			optional(report.error.clone()),
+		]);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn quotes_csv_fields_that_need_it() {
+		assert_eq!(csv_field("ms_000.tm2"), "ms_000.tm2");
+		assert_eq!(csv_field(""), "");
+		assert_eq!(csv_field("a,b"), "\"a,b\"");
+		assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+		assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+		assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+	}
+
+	#[test]
+	fn reports_frames_and_read_errors() {
+		let report = super::report(Path::new("../lib/tests/corpus/indexed8_mip3.tm2"));
+		let frame = &report.frames[0];
+
+		assert!(report.error.is_none());
+		assert_eq!(report.frames.len(), 1);
+		assert_eq!((frame.width, frame.height, frame.bpp), (32, 16, 8));
+		assert_eq!(frame.pixel_format.as_deref(), Some("Indexed8"));
+		assert_eq!(frame.mipmap_count, 3);
+		assert_eq!(frame.gs_miptbp_1, "0x0000123400080100");
+
+		let report = super::report(Path::new("../lib/tests/corpus/missing.tm2"));
+
+		assert!(report.frames.is_empty());
+		assert!(report.error.is_some());
+	}
+}
@@ -0,0 +1,195 @@
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+	path.extension()
+		.and_then(|v| v.to_str())
+		.is_some_and(|v| v.eq_ignore_ascii_case(extension))
+}
+
+/// A file to process, along with the directory holding it relative to the directory or
+/// pattern it was found through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+	pub path: PathBuf,
+	pub dir: PathBuf,
+}
+
+impl Input {
+	fn new(path: PathBuf, root: &Path) -> Input {
+		let dir = path.parent().and_then(|v| v.strip_prefix(root).ok()).map(Path::to_path_buf);
+
+		Input { dir: dir.unwrap_or_default(), path }
+	}
+
+	/// Directory to write the outputs of the file to within `output`, mirroring where it
+	/// was found so files of the same name in different directories don't collide.
+	pub fn output_dir(&self, output: Option<&Path>) -> Option<PathBuf> {
+		output.map(|v| v.join(&self.dir))
+	}
+
+	/// Path the outputs of the file are named after, before their suffixes and extension.
+	pub fn output_stem(&self, output: Option<&Path>) -> PathBuf {
+		output_path(&self.path, self.output_dir(output).as_deref(), &stem(&self.path))
+	}
+}
+
+/// Leading directories of a glob pattern, up to its first component with a wildcard.
+fn glob_root(pattern: &str) -> PathBuf {
+	Path::new(pattern)
+		.components()
+		.take_while(|v| !v.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+		.collect()
+}
+
+/// Expands file paths, directories and glob patterns into the files to process.
+///
+/// Files named directly are taken as they are. Directories are searched recursively, and
+/// both they and patterns only yield files with `extension`.
+pub fn expand(inputs: &[String], extension: &str) -> Result<Vec<Input>> {
+	let mut result = Vec::new();
+
+	for input in inputs {
+		let path = Path::new(input);
+
+		if path.is_file() {
+			result.push(Input::new(path.to_path_buf(), path.parent().unwrap_or(path)));
+		} else if path.is_dir() {
+			let mut files: Vec<PathBuf> = WalkDir::new(path)
+				.into_iter()
+				.filter_map(|entry| entry.ok())
+				.map(|entry| entry.into_path())
+				.filter(|path| path.is_file() && has_extension(path, extension))
+				.collect();
+
+			files.sort();
+			result.extend(files.into_iter().map(|v| Input::new(v, path)));
+		} else {
+			let count = result.len();
+			let root = glob_root(input);
+
+			for entry in glob::glob(input)? {
+				match entry {
+					Ok(path) if path.is_file() && has_extension(&path, extension) => result.push(Input::new(path, &root)),
+					_ => {},
+				}
+			}
+
+			if result.len() == count {
+				return Err(Error::NoMatch(input.clone()));
+			}
+		}
+	}
+
+	Ok(result)
+}
+
+/// Returns the file stem of `path`, or an empty string when it has none.
+pub fn stem(path: &Path) -> String {
+	path.file_stem().map(|v| v.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Returns where to write `file_name` for `input`: inside `output` when given, next to the
+/// input otherwise.
+pub fn output_path(input: &Path, output: Option<&Path>, file_name: &str) -> PathBuf {
+	match output {
+		Some(dir) => dir.join(file_name),
+		None => input.with_file_name(file_name),
+	}
+}
+
+/// Names the outputs of each frame after the input, numbering them when there are several.
+pub fn frame_stem(input: &Path, index: usize, count: usize) -> String {
+	if count > 1 {
+		format!("{}_{}", stem(input), index)
+	} else {
+		stem(input)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	fn touch(dir: &Path, names: &[&str]) {
+		for name in names {
+			let path = dir.join(name);
+
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, b"").unwrap();
+		}
+	}
+
+	fn input(path: PathBuf) -> String {
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn expands_files_directories_and_globs() {
+		let dir = tempfile::tempdir().unwrap();
+
+		touch(dir.path(), &["b.tm2", "a.TM2", "notes.txt", "sub/c.tm2", "sub/d.png"]);
+
+		let named = input(dir.path().join("notes.txt"));
+		let pattern = input(dir.path().join("*"));
+		let inputs = [named, input(dir.path().to_path_buf()), pattern];
+		let result: Vec<PathBuf> = expand(&inputs, "tm2").unwrap().into_iter().map(|v| v.path).collect();
+		let expected = ["notes.txt", "a.TM2", "b.tm2", "sub/c.tm2", "a.TM2", "b.tm2"];
+
+		assert_eq!(result, expected.map(|v| dir.path().join(v)));
+	}
+
+	#[test]
+	fn mirrors_subdirectories_under_the_output() {
+		let dir = tempfile::tempdir().unwrap();
+
+		touch(dir.path(), &["in/top.tm2", "in/a/x.tm2", "in/b/x.tm2", "in/b/deep/y.tm2"]);
+
+		let root = dir.path().join("in");
+		let outputs = |pattern: PathBuf| -> Vec<PathBuf> {
+			let inputs = expand(&[input(pattern)], "tm2").unwrap();
+			let mut result: Vec<PathBuf> = inputs
+				.iter()
+				.map(|v| v.output_dir(Some(Path::new("out"))).unwrap().join(v.path.file_name().unwrap()))
+				.collect();
+
+			result.sort();
+			result
+		};
+
+		let expected = ["out/a/x.tm2", "out/b/deep/y.tm2", "out/b/x.tm2", "out/top.tm2"].map(PathBuf::from);
+
+		assert_eq!(outputs(root.clone()), expected);
+		assert_eq!(outputs(root.join("**/*.tm2")), expected);
+		assert_eq!(outputs(root.join("*/x.tm2")), [expected[0].clone(), expected[2].clone()]);
+		assert_eq!(outputs(root.join("a/x.tm2")), [PathBuf::from("out/x.tm2")]);
+		assert_eq!(expand(&[input(root)], "tm2").unwrap()[1].output_dir(None), None);
+	}
+
+	#[test]
+	fn fails_on_patterns_without_matches() {
+		let dir = tempfile::tempdir().unwrap();
+
+		touch(dir.path(), &["a.png"]);
+
+		let patterns = [input(dir.path().join("*.tm2"))];
+
+		assert!(matches!(expand(&patterns, "tm2"), Err(Error::NoMatch(v)) if v == patterns[0]));
+		assert!(matches!(expand(&[input(dir.path().join("missing.tm2"))], "tm2"), Err(Error::NoMatch(_))));
+		assert!(expand(&[input(dir.path().to_path_buf())], "tm2").unwrap().is_empty());
+	}
+
+	#[test]
+	fn names_outputs_after_the_input() {
+		let input = Path::new("assets/ms_000.tm2");
+
+		assert_eq!(output_path(input, None, "ms_000.png"), Path::new("assets/ms_000.png"));
+		assert_eq!(output_path(input, Some(Path::new("out")), "ms_000.png"), Path::new("out/ms_000.png"));
+		assert_eq!(frame_stem(input, 0, 1), "ms_000");
+		assert_eq!(frame_stem(input, 2, 3), "ms_000_2");
+		assert_eq!(stem(Path::new("")), "");
+	}
+}
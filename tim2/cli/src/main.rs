@@ -1,82 +1,311 @@
-use image::ColorType;
+mod batch;
+mod diff;
+mod error;
+mod info;
+mod input;
+mod palettes;
+mod png;
+mod sheet;
+mod validate;
+
+use clap::{Args, Parser, Subcommand};
+use error::Result;
+use info::InfoFormat;
+use input::Input;
+use palettes::PaletteKind;
+use png::{Pack, PixelFormat};
 use std::fs;
-use std::io;
 use std::path::{Path, PathBuf};
-use std::result;
-use tim2::{Frame, Pixel};
+use std::process;
+use tim2::QuantizeOptions;
 
-pub type Result<T> = result::Result<T, Error>;
+/// Inspects and converts TIM2 (.tm2) image files.
+///
+/// Inputs can be files, directories, which are searched recursively, or glob patterns.
+/// Exits with 0 on success, 1 when any input failed or differed, and 2 on bad usage.
+#[derive(Parser)]
+#[command(name = "tim2")]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
 
-#[derive(Debug)]
-pub enum Error {
-	Io(io::Error),
-	Tim2(tim2::Error),
+#[derive(Args)]
+struct Inputs {
+	/// Files, directories or glob patterns to process.
+	#[arg(required = true)]
+	inputs: Vec<String>,
 }
 
-impl From<io::Error> for Error {
-	fn from(err: io::Error) -> Error {
-		Error::Io(err)
-	}
+#[derive(Args)]
+struct Output {
+	/// Directory to write to instead of next to each input, keeping the subdirectories
+	/// files were found in.
+	#[arg(short, long)]
+	output: Option<PathBuf>,
 }
 
-impl From<tim2::Error> for Error {
-	fn from(err: tim2::Error) -> Error {
-		Error::Tim2(err)
-	}
+#[derive(Args)]
+struct Batch {
+	/// Number of files to convert at once, 0 for one per core.
+	#[arg(short, long, default_value_t = 0)]
+	jobs: usize,
+
+	/// File to also write the summary of failures to, as Markdown.
+	#[arg(long)]
+	summary: Option<PathBuf>,
 }
 
-pub fn write_png(path: &Path, frame: &Frame) -> Result<()> {
-	let color_key = Some(Pixel::from(0, 255, 0, 255));
+#[derive(Subcommand)]
+enum Command {
+	/// Prints a summary of the headers of TIM2 files.
+	Info {
+		#[command(flatten)]
+		inputs: Inputs,
+
+		/// Prints every header field, offset and size.
+		#[arg(short, long, conflicts_with = "format")]
+		verbose: bool,
+
+		/// Prints a summary, or one JSON document or CSV table for all files.
+		#[arg(short, long, value_enum, default_value = "text")]
+		format: InfoFormat,
+	},
+
+	/// Converts every frame and mipmap level of TIM2 files to PNG.
+	ToPng {
+		#[command(flatten)]
+		inputs: Inputs,
+
+		#[command(flatten)]
+		output: Output,
+
+		#[command(flatten)]
+		batch: Batch,
+
+		/// Also writes the image with each of the other palettes.
+		#[arg(long)]
+		palettes: bool,
+
+		/// Keeps the color key opaque instead of making it transparent.
+		#[arg(long)]
+		no_color_key: bool,
+
+		/// Packs every frame, and every palette with `--palettes`, into one image.
+		#[arg(long, value_enum)]
+		pack: Option<Pack>,
+
+		/// Milliseconds each frame of an animation is shown for.
+		#[arg(long, default_value_t = 100, requires = "pack")]
+		delay: u16,
+	},
+
+	/// Converts PNG files to TIM2, or puts edited PNGs back into their original files.
+	///
+	/// The mipmap level and palette PNGs `to-png` writes are skipped. Without `--output`,
+	/// existing files are never replaced.
+	FromPng {
+		#[command(flatten)]
+		inputs: Inputs,
+
+		#[command(flatten)]
+		output: Output,
+
+		#[command(flatten)]
+		batch: Batch,
+
+		/// Pixel format to write.
+		#[arg(short, long, value_enum, default_value = "indexed8", conflicts_with = "template")]
+		format: PixelFormat,
+
+		/// Dithers images with too many colors for an indexed format.
+		#[arg(long, conflicts_with = "template")]
+		dither: bool,
+
+		/// Original file to take the format, CLUT, storage, GS registers and user data from,
+		/// or a directory holding one named after each PNG.
+		#[arg(short, long)]
+		template: Option<PathBuf>,
+
+		/// Frame of the template to replace, by default N for PNGs named `{name}_{N}` and
+		/// 0 for others.
+		#[arg(long, requires = "template")]
+		frame: Option<usize>,
+
+		/// Takes pixels as they are, for PNGs written with `to-png --no-color-key`.
+		#[arg(long, requires = "template")]
+		no_color_key: bool,
+	},
+
+	/// Writes the palettes of TIM2 files.
+	ExtractPalettes {
+		#[command(flatten)]
+		inputs: Inputs,
 
-	if frame.header().has_mipmaps() {
-		println!("MIPMAPS: {:?}", &path);
+		#[command(flatten)]
+		output: Output,
+
+		#[command(flatten)]
+		batch: Batch,
+
+		/// Palette file format to write.
+		#[arg(short, long, value_enum, default_value = "pal")]
+		format: PaletteKind,
+	},
+
+	/// Checks the headers of TIM2 files, failing when any has errors.
+	Validate {
+		#[command(flatten)]
+		inputs: Inputs,
+	},
+
+	/// Compares two TIM2 files frame by frame, failing when they differ.
+	Diff {
+		left: PathBuf,
+		right: PathBuf,
+
+		/// Directory to write a PNG of the differing pixels of each frame to.
+		#[arg(short, long)]
+		output: Option<PathBuf>,
+	},
+}
+
+/// Runs `f` on every file the inputs expand to, reporting failures without stopping.
+/// Returns false when any of them failed or reported a problem.
+fn process<F>(inputs: &Inputs, extension: &str, output: Option<&Path>, mut f: F) -> Result<bool>
+where
+	F: FnMut(&Path) -> Result<bool>,
+{
+	if let Some(dir) = output {
+		fs::create_dir_all(dir)?;
 	}
 
-	if !frame.header().has_mipmaps() {
-		let width = frame.header().width() as u32;
-		let height = frame.header().height() as u32;
-		let raw_pixels = frame.to_raw(color_key);
-		let mut output_path = PathBuf::from(&path);
+	let mut success = true;
+
+	for input in input::expand(&inputs.inputs, extension)? {
+		let path = input.path;
 
-		output_path.set_extension("png");
-		image::save_buffer(output_path, &raw_pixels, width, height, ColorType::Rgba8).unwrap();
+		match f(&path) {
+			Ok(v) => success &= v,
+			Err(err) => {
+				eprintln!("{}: {}", path.display(), err);
+				success = false;
+			},
+		}
 	}
 
-	Ok(())
+	Ok(success)
 }
 
-fn process_entry(path: &Path) -> Result<()> {
-	println!("Processing: {:?}", &path);
+/// Converts every file in parallel, then prints a summary of the failures grouped by kind.
+/// Returns false when any file failed.
+///
+/// `f` gets the directory to write each file's outputs to, which mirrors the directories
+/// the file was found in under `output`.
+fn convert<F>(title: &str, inputs: Vec<Input>, output: Option<&Path>, batch: &Batch, f: F) -> Result<bool>
+where
+	F: Fn(&Path, Option<&Path>) -> Result<()> + Sync,
+{
+	let failures = batch::run(&inputs, output, batch.jobs, |input| {
+		let dir = input.output_dir(output);
+
+		if let Some(dir) = &dir {
+			fs::create_dir_all(dir)?;
+		}
 
-	let img = tim2::load(path)?;
+		f(&input.path, dir.as_deref())
+	})?;
 
-	if img.frames().len() > 1 {
-		let stem = path.file_stem().unwrap().to_str().unwrap();
-		let ext = path.extension().unwrap().to_str().unwrap();
-		let mut path = PathBuf::from(path);
+	let summary = batch::summarize(title, inputs.len(), &failures);
 
-		img.frames().iter().enumerate().for_each(|(i, frame)| {
-			let filename = format!("{}_{}.{}", stem, i, ext);
+	print!("{}", summary);
 
-			path.set_file_name(filename);
-			write_png(&path, &frame).unwrap();
-		})
-	} else {
-		write_png(&path, &img.get_frame(0))?;
+	if let Some(path) = &batch.summary {
+		fs::write(path, &summary)?;
 	}
 
-	Ok(())
+	Ok(failures.is_empty())
+}
+
+fn run(command: Command) -> Result<bool> {
+	match command {
+		Command::Info { inputs, verbose, format } => match format {
+			InfoFormat::Text => process(&inputs, "tm2", None, |path| info::info(path, verbose).map(|_| true)),
+			InfoFormat::Json => {
+				let mut reports = Vec::new();
+				let success = process(&inputs, "tm2", None, |path| {
+					reports.push(info::report(path));
+					Ok(reports.last().is_some_and(|v| v.error.is_none()))
+				})?;
+
+				info::print_json(&reports);
+				Ok(success)
+			},
+			InfoFormat::Csv => {
+				info::print_csv_header();
+
+				process(&inputs, "tm2", None, |path| {
+					let report = info::report(path);
+
+					info::print_csv(&report);
+					Ok(report.error.is_none())
+				})
+			},
+		},
+		Command::ToPng { inputs, output, batch, palettes, no_color_key, pack, delay } => {
+			let output = output.output.as_deref();
+			let color_key = if no_color_key { None } else { Some(tim2::COLOR_KEY) };
+
+			convert("TM2 to PNG", input::expand(&inputs.inputs, "tm2")?, output, &batch, |path, output| match pack {
+				Some(pack) => png::pack(path, output, pack, palettes, color_key, delay),
+				None => png::to_png(path, output, palettes, color_key),
+			})
+		},
+		Command::FromPng { inputs, output, batch, format, dither, template, frame, no_color_key } => {
+			let output = output.output.as_deref();
+			let options = QuantizeOptions { dither, ..QuantizeOptions::default() };
+			let color_key = if no_color_key { None } else { Some(tim2::COLOR_KEY) };
+
+			let inputs = input::expand(&inputs.inputs, "png")?
+				.into_iter()
+				.filter(|v| !png::is_level_or_palette(&v.path))
+				.collect();
+
+			convert("PNG to TM2", inputs, output, &batch, |path, output| match &template {
+				Some(template) => png::from_png_with_template(path, output, template, frame, color_key),
+				None => png::from_png(path, output, format.into(), options),
+			})
+		},
+		Command::ExtractPalettes { inputs, output, batch, format } => {
+			let output = output.output.as_deref();
+
+			convert("Palette extraction", input::expand(&inputs.inputs, "tm2")?, output, &batch, |path, output| {
+				palettes::extract_palettes(path, output, format)
+			})
+		},
+		Command::Validate { inputs } => process(&inputs, "tm2", None, validate::validate),
+		Command::Diff { left, right, output } => {
+			if let Some(dir) = &output {
+				fs::create_dir_all(dir)?;
+			}
+
+			diff::diff(&left, &right, output.as_deref())
+		},
+	}
 }
 
 fn main() {
-	fs::read_dir("../assets")
-		.unwrap()
-		.filter_map(|entry| entry.ok())
-		.filter(|entry| entry.path().extension().unwrap() == "tm2")
-		.for_each(|entry| {
-			match process_entry(&entry.path()) {
-				Ok(_) => {},
-				Err(err) => println!("{:#?}", err),
-			};
-		});
+	let cli = Cli::parse();
+
+	let code = match run(cli.command) {
+		Ok(true) => 0,
+		Ok(false) => 1,
+		Err(err) => {
+			eprintln!("{}", err);
+
+			if err.is_usage() { 2 } else { 1 }
+		},
+	};
+
+	process::exit(code);
 }
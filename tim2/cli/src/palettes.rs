@@ -0,0 +1,53 @@
+use crate::error::Result;
+use crate::input::{frame_stem, output_path};
+
+use std::fs;
+use std::path::Path;
+use tim2::PaletteFormat;
+
+/// File formats `extract-palettes` writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum PaletteKind {
+	/// Paint Shop Pro palette.
+	Pal,
+
+	/// GIMP palette.
+	Gpl,
+
+	/// Adobe color table.
+	Act,
+
+	/// PNG strip 16 colors wide, keeping alpha.
+	Png,
+}
+
+/// Writes every palette of every frame of a TIM2 file.
+pub fn extract_palettes(input: &Path, output: Option<&Path>, kind: PaletteKind) -> Result<()> {
+	let image = tim2::load(input)?;
+	let count = image.frames().len();
+
+	for (i, frame) in image.frames().iter().enumerate() {
+		let name = frame_stem(input, i, count);
+
+		for (j, palette) in frame.palettes().iter().enumerate() {
+			let format = match kind {
+				PaletteKind::Pal => PaletteFormat::Jasc,
+				PaletteKind::Gpl => PaletteFormat::Gimp,
+				PaletteKind::Act => PaletteFormat::Act,
+				PaletteKind::Png => {
+					let path = output_path(input, output, &format!("{}_pal{}.png", name, j));
+
+					tim2::palette_to_swatch(palette).save(path)?;
+					continue;
+				},
+			};
+
+			let palette_name = format!("{}_pal{}", name, j);
+			let path = output_path(input, output, &format!("{}.{}", palette_name, format.extension()));
+
+			fs::write(path, tim2::export_palette(palette, &palette_name, format))?;
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,341 @@
+use crate::error::{Error, Result};
+use crate::input::{frame_stem, output_path, stem};
+use crate::sheet;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tim2::{Format, Frame, Image, Pixel, QuantizeOptions};
+
+/// Pixel formats `from-png` writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum PixelFormat {
+	Indexed4,
+	Indexed8,
+	Abgr1555,
+	Rgb888,
+	Rgba8888,
+}
+
+impl From<PixelFormat> for Format {
+	fn from(format: PixelFormat) -> Format {
+		match format {
+			PixelFormat::Indexed4 => Format::Indexed4,
+			PixelFormat::Indexed8 => Format::Indexed8,
+			PixelFormat::Abgr1555 => Format::Abgr1555,
+			PixelFormat::Rgb888 => Format::Rgb888,
+			PixelFormat::Rgba8888 => Format::Rgba8888,
+		}
+	}
+}
+
+/// Writes every mipmap level of a frame, and its other palettes when `all_palettes` is set.
+fn write_png(input: &Path, output: Option<&Path>, name: &str, frame: &Frame, all_palettes: bool, color_key: Option<Pixel>) -> Result<()> {
+	let mut level = 0;
+
+	while let Some(image) = frame.mip_level_to_rgba_image(level, color_key) {
+		let file_name = match level {
+			0 => format!("{}.png", name),
+			n => format!("{}_mip{}.png", name, n),
+		};
+
+		image.save(output_path(input, output, &file_name))?;
+		level += 1;
+	}
+
+	if all_palettes {
+		for i in 1..frame.palettes().len() {
+			let image = frame.to_rgba_image_with_palette(i, color_key)?;
+
+			image.save(output_path(input, output, &format!("{}_pal{}.png", name, i)))?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Converts every frame of a TIM2 file to PNG.
+pub fn to_png(input: &Path, output: Option<&Path>, all_palettes: bool, color_key: Option<Pixel>) -> Result<()> {
+	let image = tim2::load(input)?;
+	let count = image.frames().len();
+
+	for (i, frame) in image.frames().iter().enumerate() {
+		write_png(input, output, &frame_stem(input, i, count), frame, all_palettes, color_key)?;
+	}
+
+	Ok(())
+}
+
+/// Ways to pack every frame of a file into one output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum Pack {
+	/// Labeled grid of every frame in one PNG.
+	Sheet,
+
+	/// Looping animated GIF.
+	Gif,
+
+	/// Looping animated PNG.
+	Apng,
+}
+
+/// Packs every frame of a TIM2 file, and every palette when `all_palettes` is set, into
+/// one image named after the input.
+pub fn pack(input: &Path, output: Option<&Path>, pack: Pack, all_palettes: bool, color_key: Option<Pixel>, delay: u16) -> Result<()> {
+	let image = tim2::load(input)?;
+	let cells = sheet::collect_cells(&image, all_palettes, color_key)?;
+
+	if cells.is_empty() {
+		return Ok(());
+	}
+
+	match pack {
+		Pack::Sheet => sheet::contact_sheet(&cells).save(output_path(input, output, &format!("{}.png", stem(input))))?,
+		Pack::Gif => sheet::write_gif(&output_path(input, output, &format!("{}.gif", stem(input))), &cells, delay as u32)?,
+		Pack::Apng => sheet::write_apng(&output_path(input, output, &format!("{}.png", stem(input))), &cells, delay)?,
+	}
+
+	Ok(())
+}
+
+/// Tells the extra mipmap level and palette PNGs `to-png` writes, named `{name}_mipN` and
+/// `{name}_palN`, apart from the PNGs of frames.
+pub fn is_level_or_palette(path: &Path) -> bool {
+	let stem = stem(path);
+	let suffix = stem.rsplit_once('_').map_or("", |v| v.1);
+
+	["mip", "pal"].iter().any(|prefix| {
+		suffix
+			.strip_prefix(prefix)
+			.is_some_and(|v| !v.is_empty() && v.bytes().all(|c| c.is_ascii_digit()))
+	})
+}
+
+/// Converts a PNG to a single frame TIM2 file in `format`.
+///
+/// Without `output` the file goes next to the PNG, so an existing file there, likely the one
+/// the PNG was exported from, is left alone.
+pub fn from_png(input: &Path, output: Option<&Path>, format: Format, options: QuantizeOptions) -> Result<()> {
+	let path = output_path(input, output, &format!("{}.tm2", stem(input)));
+
+	if output.is_none() && path.exists() {
+		return Err(Error::WouldOverwrite(path));
+	}
+
+	let source = image::open(input)?;
+	let frame = Frame::from_image(&source, format, options)?;
+
+	tim2::save(&Image::new(vec![frame]), path)?;
+
+	Ok(())
+}
+
+/// Splits the frame number `to-png` appends to the PNGs of multi-frame files, as in
+/// `{name}_{N}`, off a stem.
+fn split_frame_suffix(stem: &str) -> Option<(&str, usize)> {
+	let (name, index) = stem.rsplit_once('_')?;
+
+	Some((name, index.parse().ok()?))
+}
+
+/// Finds the template of `input`, along with the frame its name points to.
+///
+/// A directory holds a file named after each PNG, or after the file the frame of a
+/// `{name}_{N}.png` came from. A template given as a file is used for every PNG.
+fn find_template(input: &Path, template: &Path) -> (PathBuf, Option<usize>) {
+	let stem = stem(input);
+	let suffix = split_frame_suffix(&stem);
+
+	if !template.is_dir() {
+		return (template.to_path_buf(), suffix.map(|v| v.1));
+	}
+
+	let path = template.join(format!("{}.tm2", stem));
+
+	match suffix {
+		Some((name, index)) if !path.exists() => (template.join(format!("{}.tm2", name)), Some(index)),
+		_ => (path, None),
+	}
+}
+
+/// Replaces a frame of a template TIM2 file with a PNG, keeping everything else about the
+/// file, and writes the result named after the PNG. Like [`from_png`], it leaves an
+/// existing file next to the PNG alone when there is no `output`.
+///
+/// Without `frame`, a PNG named `{name}_{N}` replaces frame N of a template with several
+/// frames, and any other PNG replaces frame 0.
+pub fn from_png_with_template(input: &Path, output: Option<&Path>, template: &Path, frame: Option<usize>, color_key: Option<Pixel>) -> Result<()> {
+	let (template, hint) = find_template(input, template);
+	let path = output_path(input, output, &format!("{}.tm2", stem(input)));
+
+	let is_template = fs::canonicalize(&path).ok().is_some_and(|v| fs::canonicalize(&template).ok() == Some(v));
+
+	if is_template || (output.is_none() && path.exists()) {
+		return Err(Error::WouldOverwrite(path));
+	}
+
+	let mut image = tim2::load(&template)?;
+	let index = frame.or(hint.filter(|_| image.frames().len() > 1)).unwrap_or(0);
+
+	if index >= image.frames().len() {
+		return Err(Error::NoFrame(index));
+	}
+
+	let source = image::open(input)?;
+	let frame = Frame::from_image_with_template(image.get_frame(index), &source, color_key)?;
+
+	*image.get_frame_mut(index) = frame;
+	tim2::save(&image, path)?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tim2::ClutFormat;
+
+	const MIPMAPS: &str = "../lib/tests/corpus/indexed8_mip3.tm2";
+
+	#[test]
+	fn writes_one_png_per_mip_level() {
+		let dir = tempfile::tempdir().unwrap();
+
+		to_png(Path::new(MIPMAPS), Some(dir.path()), false, None).unwrap();
+
+		for (name, size) in [("indexed8_mip3.png", (32, 16)), ("indexed8_mip3_mip1.png", (16, 8)), ("indexed8_mip3_mip2.png", (8, 4))] {
+			let image = image::open(dir.path().join(name)).unwrap();
+
+			assert_eq!((image.width(), image.height()), size, "{}", name);
+		}
+
+		assert!(!dir.path().join("indexed8_mip3_mip3.png").exists());
+	}
+
+	#[test]
+	fn writes_one_png_per_palette() {
+		let dir = tempfile::tempdir().unwrap();
+		let input = dir.path().join("two_palettes.tm2");
+		let palettes = [255, 128, 0].iter().map(|v| vec![Pixel::rgba(*v, 0, 0, 255), Pixel::rgba(0, *v, 0, 255)]).collect();
+		let frame = Frame::from_indexed(2, 1, Format::Indexed4, &[0, 1], palettes, ClutFormat::default()).unwrap();
+
+		tim2::save(&Image::new(vec![frame]), &input).unwrap();
+		to_png(&input, None, false, None).unwrap();
+		assert!(!dir.path().join("two_palettes_pal1.png").exists());
+
+		to_png(&input, None, true, None).unwrap();
+
+		for (name, v) in [("two_palettes.png", 255), ("two_palettes_pal1.png", 128), ("two_palettes_pal2.png", 0)] {
+			let image = image::open(dir.path().join(name)).unwrap().to_rgba8();
+
+			assert_eq!(image.into_raw(), vec![v, 0, 0, 255, 0, v, 0, 255], "{}", name);
+		}
+
+		assert!(!dir.path().join("two_palettes_pal0.png").exists());
+		assert!(!dir.path().join("two_palettes_pal3.png").exists());
+	}
+
+	#[test]
+	fn tells_levels_and_palettes_from_frames() {
+		assert!(is_level_or_palette(Path::new("a/ms_000_mip1.png")));
+		assert!(is_level_or_palette(Path::new("ms_000_1_pal12.png")));
+		assert!(!is_level_or_palette(Path::new("ms_000.png")));
+		assert!(!is_level_or_palette(Path::new("ms_000_1.png")));
+		assert!(!is_level_or_palette(Path::new("menu_pal.png")));
+	}
+
+	#[test]
+	fn from_png_keeps_existing_files_next_to_the_input() {
+		let dir = tempfile::tempdir().unwrap();
+		let input = dir.path().join("indexed8_mip3.png");
+		let original = dir.path().join("indexed8_mip3.tm2");
+
+		fs::copy(MIPMAPS, &original).unwrap();
+		to_png(&original, None, false, None).unwrap();
+
+		let result = from_png(&input, None, Format::Indexed8, QuantizeOptions::default());
+
+		assert!(matches!(result, Err(Error::WouldOverwrite(v)) if v == original));
+		assert_eq!(fs::read(&original).unwrap(), fs::read(MIPMAPS).unwrap());
+
+		let output = dir.path().join("out");
+
+		fs::create_dir(&output).unwrap();
+		from_png(&input, Some(&output), Format::Indexed8, QuantizeOptions::default()).unwrap();
+		assert!(output.join("indexed8_mip3.tm2").exists());
+	}
+
+	#[test]
+	fn from_png_with_template_keeps_existing_files_next_to_the_input() {
+		let dir = tempfile::tempdir().unwrap();
+		let template = dir.path().join("orig");
+		let work = dir.path().join("work");
+		let existing = work.join("x.tm2");
+
+		fs::create_dir(&template).unwrap();
+		fs::create_dir(&work).unwrap();
+		fs::copy(MIPMAPS, template.join("x.tm2")).unwrap();
+		fs::write(&existing, b"edited elsewhere").unwrap();
+		to_png(&template.join("x.tm2"), Some(&work), false, None).unwrap();
+
+		let input = work.join("x.png");
+		let result = from_png_with_template(&input, None, &template.join("x.tm2"), None, None);
+
+		assert!(matches!(result, Err(Error::WouldOverwrite(v)) if v == existing));
+		assert_eq!(fs::read(&existing).unwrap(), b"edited elsewhere");
+
+		fs::remove_file(&existing).unwrap();
+		from_png_with_template(&input, None, &template, None, None).unwrap();
+		let result = tim2::load(&existing).unwrap();
+
+		assert_eq!(result.get_frame(0).to_raw(None), tim2::load(MIPMAPS).unwrap().get_frame(0).to_raw(None));
+	}
+
+	#[test]
+	fn finds_templates_of_frames_by_their_suffix() {
+		let dir = tempfile::tempdir().unwrap();
+		let template = dir.path().join("two.tm2");
+		let frames = [[255, 0, 0, 255], [0, 0, 255, 255]]
+			.iter()
+			.map(|v| Frame::from_rgba(4, 4, Format::Rgba8888, &v.repeat(16)).unwrap())
+			.collect();
+
+		tim2::save(&Image::new(frames), &template).unwrap();
+		to_png(&template, None, false, None).unwrap();
+
+		let input = dir.path().join("two_1.png");
+		let mut edited = image::open(&input).unwrap().to_rgba8();
+
+		edited.put_pixel(0, 0, image::Rgba([0, 255, 255, 255]));
+		edited.save(&input).unwrap();
+
+		let output = dir.path().join("out");
+
+		fs::create_dir(&output).unwrap();
+		from_png_with_template(&input, Some(&output), dir.path(), None, None).unwrap();
+
+		let result = tim2::load(output.join("two_1.tm2")).unwrap();
+		let original = tim2::load(&template).unwrap();
+
+		assert_eq!(result.frames().len(), 2);
+		assert_eq!(result.get_frame(0).to_raw(None), original.get_frame(0).to_raw(None));
+		assert_eq!(result.get_frame(1).to_raw(None), edited.into_raw());
+	}
+
+	#[test]
+	fn keeps_numbers_that_are_part_of_the_name() {
+		let dir = tempfile::tempdir().unwrap();
+		let template = dir.path().join("ms_284.tm2");
+		let output = dir.path().join("out");
+		let frame = Frame::from_rgba(4, 4, Format::Rgba8888, &[255; 64]).unwrap();
+
+		tim2::save(&Image::new(vec![frame]), &template).unwrap();
+		to_png(&template, None, false, None).unwrap();
+		fs::create_dir(&output).unwrap();
+
+		let input = dir.path().join("ms_284.png");
+
+		assert_eq!(find_template(&input, dir.path()), (template.clone(), None));
+		from_png_with_template(&input, Some(&output), dir.path(), None, None).unwrap();
+		from_png_with_template(&input, Some(&output), &template, None, None).unwrap();
+	}
+}
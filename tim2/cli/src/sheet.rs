@@ -0,0 +1,232 @@
+use crate::error::Result;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Rgba, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tim2::{Frame, Image, Pixel};
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const SCALE: u32 = 2;
+const PADDING: u32 = 4;
+const LABEL_HEIGHT: u32 = GLYPH_HEIGHT * SCALE + PADDING;
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+const TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// One image to pack: a frame drawn with one of its palettes.
+pub struct Cell {
+	pub label: String,
+	pub image: RgbaImage,
+}
+
+/// Collects the cells of every frame, and of every palette of each frame when `all_palettes`
+/// is set.
+pub fn collect_cells(image: &Image, all_palettes: bool, color_key: Option<Pixel>) -> Result<Vec<Cell>> {
+	let mut result = Vec::new();
+
+	for (i, frame) in image.frames().iter().enumerate() {
+		let palette_count = if all_palettes { frame.palettes().len().max(1) } else { 1 };
+
+		for j in 0..palette_count {
+			result.push(Cell {
+				label: if all_palettes { format!("F{} P{}", i, j) } else { format!("F{}", i) },
+				image: render(frame, j, color_key)?,
+			});
+		}
+	}
+
+	Ok(result)
+}
+
+fn render(frame: &Frame, palette: usize, color_key: Option<Pixel>) -> Result<RgbaImage> {
+	match palette {
+		0 => Ok(frame.to_rgba_image(color_key)),
+		n => Ok(frame.to_rgba_image_with_palette(n, color_key)?),
+	}
+}
+
+/// Rows of a 3x5 glyph, three bits each with the leftmost column in the high bit.
+fn glyph(c: char) -> [u8; 5] {
+	match c {
+		'0' => [7, 5, 5, 5, 7],
+		'1' => [2, 6, 2, 2, 7],
+		'2' => [7, 1, 7, 4, 7],
+		'3' => [7, 1, 7, 1, 7],
+		'4' => [5, 5, 7, 1, 1],
+		'5' => [7, 4, 7, 1, 7],
+		'6' => [7, 4, 7, 5, 7],
+		'7' => [7, 1, 1, 1, 1],
+		'8' => [7, 5, 7, 5, 7],
+		'9' => [7, 5, 7, 1, 7],
+		'F' => [7, 4, 6, 4, 4],
+		'P' => [7, 5, 7, 4, 4],
+		_ => [0; 5],
+	}
+}
+
+fn draw_text(image: &mut RgbaImage, x: u32, y: u32, text: &str) {
+	for (i, c) in text.chars().enumerate() {
+		let left = x + i as u32 * (GLYPH_WIDTH + 1) * SCALE;
+
+		for (row, bits) in glyph(c).iter().enumerate() {
+			for column in 0..GLYPH_WIDTH {
+				if bits & (4 >> column) == 0 {
+					continue;
+				}
+
+				for dy in 0..SCALE {
+					for dx in 0..SCALE {
+						let px = left + column * SCALE + dx;
+						let py = y + row as u32 * SCALE + dy;
+
+						if px < image.width() && py < image.height() {
+							image.put_pixel(px, py, TEXT);
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+fn text_width(text: &str) -> u32 {
+	text.chars().count() as u32 * (GLYPH_WIDTH + 1) * SCALE
+}
+
+/// Size large enough to hold the image of every cell.
+fn max_size(cells: &[Cell]) -> (u32, u32) {
+	cells.iter().fold((1, 1), |(w, h), v| (w.max(v.image.width()), h.max(v.image.height())))
+}
+
+/// Packs the cells into a grid as close to square as possible, each labeled above its image.
+pub fn contact_sheet(cells: &[Cell]) -> RgbaImage {
+	let (width, height) = max_size(cells);
+	let label_width = cells.iter().map(|v| text_width(&v.label)).max().unwrap_or(0);
+	let cell_width = width.max(label_width) + PADDING;
+	let cell_height = height + LABEL_HEIGHT + PADDING;
+	let columns = (cells.len() as f64).sqrt().ceil().max(1.0) as u32;
+	let rows = (cells.len() as u32).div_ceil(columns).max(1);
+	let mut result = RgbaImage::from_pixel(columns * cell_width + PADDING, rows * cell_height + PADDING, BACKGROUND);
+
+	for (i, cell) in cells.iter().enumerate() {
+		let x = PADDING + (i as u32 % columns) * cell_width;
+		let y = PADDING + (i as u32 / columns) * cell_height;
+
+		draw_text(&mut result, x, y, &cell.label);
+		imageops::overlay(&mut result, &cell.image, x as i64, (y + LABEL_HEIGHT) as i64);
+	}
+
+	result
+}
+
+/// Places every cell at the top left of a transparent canvas large enough for all of them.
+fn animation_frames(cells: &[Cell]) -> Vec<RgbaImage> {
+	let (width, height) = max_size(cells);
+
+	cells
+		.iter()
+		.map(|cell| {
+			let mut canvas = RgbaImage::new(width, height);
+
+			imageops::replace(&mut canvas, &cell.image, 0, 0);
+			canvas
+		})
+		.collect()
+}
+
+/// Writes the cells as a looping GIF, showing each for `delay` milliseconds.
+pub fn write_gif(path: &Path, cells: &[Cell], delay: u32) -> Result<()> {
+	let mut encoder = GifEncoder::new(BufWriter::new(File::create(path)?));
+	let delay = Delay::from_numer_denom_ms(delay, 1);
+
+	encoder.set_repeat(Repeat::Infinite)?;
+	encoder.encode_frames(animation_frames(cells).into_iter().map(|v| image::Frame::from_parts(v, 0, 0, delay)))?;
+
+	Ok(())
+}
+
+/// Writes the cells as a looping APNG, showing each for `delay` milliseconds.
+pub fn write_apng(path: &Path, cells: &[Cell], delay: u16) -> Result<()> {
+	let frames = animation_frames(cells);
+	let (width, height) = frames.first().map_or((1, 1), |v| v.dimensions());
+	let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+	encoder.set_animated(frames.len().max(1) as u32, 0)?;
+	encoder.set_frame_delay(delay, 1000)?;
+
+	let mut writer = encoder.write_header()?;
+
+	for frame in &frames {
+		writer.write_image_data(frame.as_raw())?;
+	}
+
+	writer.finish()?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tim2::{ClutFormat, Format};
+
+	fn cells(count: u8, width: u32, height: u32) -> Vec<Cell> {
+		(0..count)
+			.map(|i| Cell {
+				label: format!("F{}", i),
+				image: RgbaImage::from_pixel(width, height, Rgba([i * 40, 255, 0, 255])),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn lays_cells_out_in_a_square_grid() {
+		let sheet = contact_sheet(&cells(5, 10, 6));
+
+		// labels are wider than the images: cells are 16 + 4 by 6 + 14 + 4 pixels, 3 to a row
+		assert_eq!(sheet.dimensions(), (3 * 20 + PADDING, 2 * 24 + PADDING));
+		assert_eq!(*sheet.get_pixel(4, 4), TEXT);
+		assert_eq!(*sheet.get_pixel(4, 4 + LABEL_HEIGHT), Rgba([0, 255, 0, 255]));
+		assert_eq!(*sheet.get_pixel(24, 28 + LABEL_HEIGHT), Rgba([160, 255, 0, 255]));
+		assert_eq!(*sheet.get_pixel(33, 33 + LABEL_HEIGHT), Rgba([160, 255, 0, 255]));
+		assert_eq!(*sheet.get_pixel(34, 28 + LABEL_HEIGHT), BACKGROUND);
+		assert_eq!(*sheet.get_pixel(44, 28 + LABEL_HEIGHT), BACKGROUND);
+	}
+
+	#[test]
+	fn sizes_cells_after_the_largest_image() {
+		let mut cells = cells(2, 4, 4);
+
+		cells[1].image = RgbaImage::new(30, 2);
+
+		assert_eq!(contact_sheet(&cells).dimensions(), (2 * 34 + PADDING, 4 + LABEL_HEIGHT + 2 * PADDING));
+		assert_eq!(contact_sheet(&[]).dimensions(), (1 + 2 * PADDING, 1 + LABEL_HEIGHT + 2 * PADDING));
+
+		let frames = animation_frames(&cells);
+
+		assert!(frames.iter().all(|v| v.dimensions() == (30, 4)));
+		assert_eq!(*frames[0].get_pixel(29, 3), Rgba([0, 0, 0, 0]));
+	}
+
+	#[test]
+	fn labels_each_frame_and_palette() {
+		let palettes = vec![vec![Pixel::rgba(255, 0, 0, 255)], vec![Pixel::rgba(0, 0, 255, 255)]];
+		let indexed = Frame::from_indexed(1, 1, Format::Indexed4, &[0], palettes, ClutFormat::default()).unwrap();
+		let direct = Frame::from_rgba(1, 1, Format::Rgba8888, &[0, 255, 0, 255]).unwrap();
+		let image = Image::new(vec![indexed, direct]);
+		let labels = |all_palettes| -> Vec<String> {
+			collect_cells(&image, all_palettes, None).unwrap().into_iter().map(|v| v.label).collect()
+		};
+
+		assert_eq!(labels(false), ["F0", "F1"]);
+		assert_eq!(labels(true), ["F0 P0", "F0 P1", "F1 P0"]);
+
+		let cells = collect_cells(&image, true, None).unwrap();
+
+		assert_eq!(cells[1].image.as_raw(), &[0, 0, 255, 255]);
+	}
+}
@@ -0,0 +1,30 @@
+use crate::error::Result;
+
+use std::fs;
+use std::path::Path;
+use tim2::Severity;
+
+/// Prints the problems found in a TIM2 file. Returns false when any of them is an error.
+pub fn validate(input: &Path) -> Result<bool> {
+	let buffer = fs::read(input)?;
+	let diagnostics = tim2::validate(&buffer);
+
+	if diagnostics.is_empty() {
+		println!("{}: ok", input.display());
+	}
+
+	for diagnostic in &diagnostics {
+		let frame = diagnostic.frame.map(|v| format!(" frame[{}]", v)).unwrap_or_default();
+
+		println!(
+			"{}:{} {:?} @{:#x}: {:?}",
+			input.display(),
+			frame,
+			diagnostic.severity,
+			diagnostic.offset,
+			diagnostic.kind
+		);
+	}
+
+	Ok(diagnostics.iter().all(|v| v.severity != Severity::Error))
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// run with `cargo +nightly fuzz run from_buffer` from tim2/lib, seeding it with tests/corpus
+fuzz_target!(|data: &[u8]| {
+	if let Ok(image) = tim2::from_buffer(data) {
+		for frame in image.frames() {
+			frame.to_raw(None);
+		}
+
+		let _ = tim2::to_buffer(&image);
+	}
+
+	let _ = tim2::inspect(data);
+	tim2::validate(data);
+});
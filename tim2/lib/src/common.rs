@@ -1,7 +1,22 @@
-pub fn get_slice<'a>(buffer: &'a [u8], offset: &mut usize, size: usize) -> &'a [u8] {
+use crate::error::{Error, Result};
+
+/// Forwards to `log::debug!` when the `log` feature is enabled, and compiles to nothing otherwise.
+macro_rules! debug {
+	($($arg:tt)*) => {
+		#[cfg(feature = "log")]
+		log::debug!($($arg)*);
+	};
+}
+
+pub(crate) use debug;
+
+pub fn get_slice<'a>(buffer: &'a [u8], offset: &mut usize, size: usize) -> Result<&'a [u8]> {
 	let start_index = *offset;
-	let end_index = start_index + size;
+	let end_index = start_index
+		.checked_add(size)
+		.filter(|v| *v <= buffer.len())
+		.ok_or(Error::UnexpectedEof { offset: start_index, needed: size })?;
 
-	*offset += size;
-	&buffer[start_index..end_index]
+	*offset = end_index;
+	Ok(&buffer[start_index..end_index])
 }
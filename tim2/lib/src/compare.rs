@@ -0,0 +1,199 @@
+use crate::frame::{Frame, Header};
+use crate::pixel::Pixel;
+
+const MISMATCH_COLOR: [u8; 4] = [255, 0, 0, 255];
+
+/// Options for [`compare_with_options`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CompareOptions {
+	/// Renders [`FrameDiff::diff_image`].
+	pub diff_image: bool,
+}
+
+/// A header field holding different values in the two frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+	pub name: &'static str,
+	pub left: u64,
+	pub right: u64,
+}
+
+/// A palette entry holding different colors in the two frames, or only present in one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PaletteDiff {
+	pub palette: usize,
+	pub index: usize,
+	pub left: Option<Pixel>,
+	pub right: Option<Pixel>,
+}
+
+/// Differences between two frames.
+///
+/// Pixels are compared after decoding to RGBA8, so frames with different formats or storage
+/// modes still match when they show the same image. When the sizes differ, both images are
+/// placed on a canvas large enough for either, padded with transparent black.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameDiff {
+	pub width: u32,
+	pub height: u32,
+	pub header: Vec<FieldDiff>,
+	pub user_data_differs: bool,
+	pub palettes: Vec<PaletteDiff>,
+
+	/// Number of pixels whose RGBA8 values differ.
+	pub mismatched_pixels: usize,
+
+	/// Peak signal to noise ratio over all RGBA8 channels, infinite for identical images.
+	pub psnr: f64,
+
+	/// RGBA8 image with mismatched pixels in red and matching ones dimmed to gray.
+	pub diff_image: Option<Vec<u8>>,
+}
+
+impl FrameDiff {
+	/// Returns true when the frames are equal in every compared respect.
+	pub fn is_identical(&self) -> bool {
+		self.header.is_empty() && !self.user_data_differs && self.palettes.is_empty() && self.mismatched_pixels == 0
+	}
+}
+
+fn header_fields(header: &Header) -> [(&'static str, u64); 17] {
+	[
+		("total_size", header.total_size() as u64),
+		("clut_size", header.clut_size() as u64),
+		("image_size", header.image_size() as u64),
+		("header_size", header.header_size() as u64),
+		("clut_color_count", header.clut_color_count() as u64),
+		("picture_format", header.picture_format() as u64),
+		("mipmap_count", header.mipmap_count() as u64),
+		("clut_format", header.clut_format() as u64),
+		("bpp", header.bpp() as u64),
+		("width", header.width() as u64),
+		("height", header.height() as u64),
+		("gs_regs", header.gs_regs() as u64),
+		("gs_tex_clut", header.gs_tex_clut() as u64),
+		("gs_tex_0", header.gs_tex_0()),
+		("gs_tex_1", header.gs_tex_1()),
+		("gs_miptbp_1", header.gs_miptbp_1()),
+		("gs_miptbp_2", header.gs_miptbp_2()),
+	]
+}
+
+fn compare_headers(left: &Header, right: &Header) -> Vec<FieldDiff> {
+	header_fields(left)
+		.iter()
+		.zip(header_fields(right).iter())
+		.filter(|(l, r)| l.1 != r.1)
+		.map(|(l, r)| FieldDiff {
+			name: l.0,
+			left: l.1,
+			right: r.1,
+		})
+		.collect()
+}
+
+fn compare_palettes(left: &Frame, right: &Frame) -> Vec<PaletteDiff> {
+	let mut result = Vec::new();
+	let palette_count = left.palettes().len().max(right.palettes().len());
+
+	for palette in 0..palette_count {
+		let left = left.palettes().get(palette);
+		let right = right.palettes().get(palette);
+		let color_count = left.map_or(0, |v| v.len()).max(right.map_or(0, |v| v.len()));
+
+		for index in 0..color_count {
+			let left = left.and_then(|v| v.get(index)).copied();
+			let right = right.and_then(|v| v.get(index)).copied();
+
+			if left != right {
+				result.push(PaletteDiff { palette, index, left, right });
+			}
+		}
+	}
+
+	result
+}
+
+fn texel(raw: &[u8], width: usize, height: usize, x: usize, y: usize) -> [u8; 4] {
+	if x < width && y < height {
+		let offset = (y * width + x) * 4;
+
+		[raw[offset], raw[offset + 1], raw[offset + 2], raw[offset + 3]]
+	} else {
+		[0; 4]
+	}
+}
+
+fn dim(color: [u8; 4]) -> [u8; 4] {
+	let luma = (color[0] as u32 * 77 + color[1] as u32 * 150 + color[2] as u32 * 29) >> 8;
+	let value = (luma / 4) as u8;
+
+	[value, value, value, 255]
+}
+
+/// Compares two frames, without rendering a diff image.
+///
+/// ```
+/// let left = tim2::load("../assets/test.tm2").unwrap();
+/// let right = tim2::load("../assets/test.tm2").unwrap();
+/// let diff = tim2::compare(left.get_frame(0), right.get_frame(0));
+///
+/// assert!(diff.is_identical());
+/// assert_eq!(diff.psnr, f64::INFINITY);
+/// ```
+pub fn compare(left: &Frame, right: &Frame) -> FrameDiff {
+	compare_with_options(left, right, CompareOptions::default())
+}
+
+/// Compares two frames, rendering a diff image when `options` asks for one.
+pub fn compare_with_options(left: &Frame, right: &Frame, options: CompareOptions) -> FrameDiff {
+	let left_width = left.header().width() as usize;
+	let left_height = left.header().height() as usize;
+	let right_width = right.header().width() as usize;
+	let right_height = right.header().height() as usize;
+	let width = left_width.max(right_width);
+	let height = left_height.max(right_height);
+	let left_raw = left.to_raw(None);
+	let right_raw = right.to_raw(None);
+	let mut diff_image = Vec::with_capacity(if options.diff_image { width * height * 4 } else { 0 });
+	let mut mismatched_pixels = 0;
+	let mut squared_error = 0u64;
+
+	for y in 0..height {
+		for x in 0..width {
+			let l = texel(&left_raw, left_width, left_height, x, y);
+			let r = texel(&right_raw, right_width, right_height, x, y);
+
+			for (a, b) in l.iter().zip(r.iter()) {
+				let delta = *a as i64 - *b as i64;
+
+				squared_error += (delta * delta) as u64;
+			}
+
+			if l != r {
+				mismatched_pixels += 1;
+			}
+
+			if options.diff_image {
+				diff_image.extend_from_slice(&if l == r { dim(l) } else { MISMATCH_COLOR });
+			}
+		}
+	}
+
+	let channel_count = (width * height * 4).max(1) as f64;
+	let psnr = match squared_error {
+		0 => f64::INFINITY,
+		n => 10.0 * (255.0 * 255.0 / (n as f64 / channel_count)).log10(),
+	};
+
+	FrameDiff {
+		width: width as u32,
+		height: height as u32,
+		header: compare_headers(left.header(), right.header()),
+		user_data_differs: left.header().user_data() != right.header().user_data(),
+		palettes: compare_palettes(left, right),
+		mismatched_pixels,
+		psnr,
+		diff_image: if options.diff_image { Some(diff_image) } else { None },
+	}
+}
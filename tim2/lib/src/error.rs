@@ -1,3 +1,7 @@
+use crate::pixel::Format;
+
+use std::error;
+use std::fmt;
 use std::io;
 use std::result;
 
@@ -10,6 +14,21 @@ pub enum Error {
 	InvalidBpp(u8),
 	InvalidBppFormat(u8),
 	InvalidPixelSize(usize),
+	InvalidBufferSize(usize, usize),
+	InvalidPaletteCount(usize),
+	InvalidPaletteIndex(usize),
+	IndexOutOfRange(u8),
+	TooManyColors(usize),
+	InvalidColorCount(usize),
+	InvalidDimensions(u32, u32),
+	UnsupportedFormat(Format),
+	UnexpectedEof { offset: usize, needed: usize },
+	HeaderSizeTooSmall(u16),
+	ZeroClutColorCount,
+	InvalidClutSize(u32),
+	MipmapSizesTooLarge(u64, u32),
+	UserDataTooLarge(usize, usize),
+	MalformedPalette(usize),
 	Io(io::Error),
 	TrueColorAndPaletteFound,
 }
@@ -19,3 +38,76 @@ impl From<io::Error> for Error {
 		Error::Io(err)
 	}
 }
+
+impl Error {
+	/// Name of the variant, for grouping errors by kind.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Error::InvalidIdentifier(_) => "InvalidIdentifier",
+			Error::InvalidAlignment(_) => "InvalidAlignment",
+			Error::InvalidBpp(_) => "InvalidBpp",
+			Error::InvalidBppFormat(_) => "InvalidBppFormat",
+			Error::InvalidPixelSize(_) => "InvalidPixelSize",
+			Error::InvalidBufferSize(..) => "InvalidBufferSize",
+			Error::InvalidPaletteCount(_) => "InvalidPaletteCount",
+			Error::InvalidPaletteIndex(_) => "InvalidPaletteIndex",
+			Error::IndexOutOfRange(_) => "IndexOutOfRange",
+			Error::TooManyColors(_) => "TooManyColors",
+			Error::InvalidColorCount(_) => "InvalidColorCount",
+			Error::InvalidDimensions(..) => "InvalidDimensions",
+			Error::UnsupportedFormat(_) => "UnsupportedFormat",
+			Error::UnexpectedEof { .. } => "UnexpectedEof",
+			Error::HeaderSizeTooSmall(_) => "HeaderSizeTooSmall",
+			Error::ZeroClutColorCount => "ZeroClutColorCount",
+			Error::InvalidClutSize(_) => "InvalidClutSize",
+			Error::MipmapSizesTooLarge(..) => "MipmapSizesTooLarge",
+			Error::UserDataTooLarge(..) => "UserDataTooLarge",
+			Error::MalformedPalette(_) => "MalformedPalette",
+			Error::Io(_) => "Io",
+			Error::TrueColorAndPaletteFound => "TrueColorAndPaletteFound",
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::InvalidIdentifier(v) => write!(f, "not a TIM2 file, identifier is {:#010x}", v),
+			Error::InvalidAlignment(v) => write!(f, "invalid alignment flag {}", v),
+			Error::InvalidBpp(v) => write!(f, "unsupported bit depth of {} bits per pixel", v),
+			Error::InvalidBppFormat(v) => write!(f, "invalid pixel format {}", v),
+			Error::InvalidPixelSize(v) => write!(f, "invalid color size of {} bytes", v),
+			Error::InvalidBufferSize(expected, found) => write!(f, "expected {} pixels or bytes, found {}", expected, found),
+			Error::InvalidPaletteCount(v) => write!(f, "invalid palette count {}", v),
+			Error::InvalidPaletteIndex(v) => write!(f, "no palette at index {}", v),
+			Error::IndexOutOfRange(v) => write!(f, "palette index {} is out of range", v),
+			Error::TooManyColors(v) => write!(f, "{} colors don't fit in the palette", v),
+			Error::InvalidColorCount(v) => write!(f, "invalid palette size of {} colors", v),
+			Error::InvalidDimensions(width, height) => write!(f, "invalid dimensions {}x{}", width, height),
+			Error::UnsupportedFormat(v) => write!(f, "unsupported format {:?}", v),
+			Error::UnexpectedEof { offset, needed } => write!(f, "unexpected end of data reading {} bytes at {:#x}", needed, offset),
+			Error::HeaderSizeTooSmall(v) => write!(f, "frame header size {} is too small", v),
+			Error::ZeroClutColorCount => write!(f, "CLUT has no colors"),
+			Error::InvalidClutSize(v) => write!(f, "CLUT size {} holds no whole palette", v),
+			Error::MipmapSizesTooLarge(sizes, image_size) => {
+				write!(f, "mipmap levels take {} bytes, more than the {} bytes of image data", sizes, image_size)
+			},
+			Error::UserDataTooLarge(size, available) => {
+				write!(f, "user data takes {} bytes, more than the {} bytes the header leaves for it", size, available)
+			},
+			Error::MalformedPalette(0) => write!(f, "palette file ends early"),
+			Error::MalformedPalette(line) => write!(f, "malformed palette file at line {}", line),
+			Error::Io(err) => write!(f, "{}", err),
+			Error::TrueColorAndPaletteFound => write!(f, "true color frame has a CLUT"),
+		}
+	}
+}
+
+impl error::Error for Error {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			Error::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
@@ -1,14 +1,49 @@
 use crate::common::*;
 use crate::error::{Error, Result};
-use crate::pixel::{Format, Pixel};
+use crate::gs::{MipTbp, Tex0, Tex1, TexClut};
+use crate::pixel::{DecodeOptions, Format, Pixel};
+use crate::quantize::{quantize, QuantizeOptions};
+use crate::storage::Storage;
+use crate::user_data::UserData;
 
-use byteorder::{ByteOrder, LittleEndian};
-
-const SWIZZLE_WIDTH: usize = 16;
-const SWIZZLE_HEIGHT: usize = 8;
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use std::collections::HashMap;
+use std::io::Write;
 
 pub type PixelBuffer = Vec<Pixel>;
 
+/// Describes how the colors of a frame's CLUT are stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClutFormat {
+	/// Color format of each CLUT entry: `Abgr1555`, `Rgb888` or `Rgba8888`.
+	pub color: Format,
+
+	/// Stores 256-color palettes in linear order instead of the CSM1 block order.
+	pub linear: bool,
+}
+
+impl ClutFormat {
+	fn to_raw(self) -> Result<u8> {
+		let kind = match self.color {
+			Format::Abgr1555 => 1,
+			Format::Rgb888 => 2,
+			Format::Rgba8888 => 3,
+			n => return Err(Error::UnsupportedFormat(n)),
+		};
+
+		Ok(if self.linear { kind | 0x80 } else { kind })
+	}
+}
+
+impl Default for ClutFormat {
+	fn default() -> Self {
+		ClutFormat {
+			color: Format::Rgba8888,
+			linear: false,
+		}
+	}
+}
+
 #[derive(Debug)]
 pub enum DataKind {
 	Indices(Vec<u8>),
@@ -22,9 +57,13 @@ impl DataKind {
 			DataKind::Pixels(v) => v.len(),
 		}
 	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Header {
 	total_size: u32,
 	clut_size: u32,
@@ -41,35 +80,98 @@ pub struct Header {
 	gs_tex_clut: u32,
 	gs_tex_0: u64,
 	gs_tex_1: u64,
-	user_data: Vec<u8>,
+	gs_miptbp_1: u64,
+	gs_miptbp_2: u64,
+	mipmap_sizes: Vec<u32>,
+	user_data: UserData,
 }
 
 impl Header {
-	fn read(buffer: &[u8], offset: &mut usize) -> Result<Header> {
-		let mut load_part = |size| { get_slice(&buffer, offset, size) };
+	fn new(width: u16, height: u16, format: Format, clut: ClutFormat, palettes: &[PixelBuffer]) -> Result<Header> {
+		let bpp = format.bpp();
+		let storage = Storage::detect(&Tex0::from_raw(0), bpp, width, height);
+		let image_size = storage.stored_len(width, height, bpp) * bpp as usize / 8;
+		let clut_color_count = palettes.first().map_or(0, |v| v.len());
+		let (clut_format, clut_size) = if palettes.is_empty() {
+			(0, 0)
+		} else {
+			let color_size = clut.color.bpp() as usize / 8;
+
+			(clut.to_raw()?, clut_color_count * color_size * palettes.len())
+		};
+
+		Ok(Header {
+			total_size: (48 + image_size + clut_size) as u32,
+			clut_size: clut_size as u32,
+			image_size: image_size as u32,
+			header_size: 48,
+			clut_color_count: clut_color_count as u16,
+			picture_format: 0,
+			mipmap_count: 1,
+			clut_format,
+			bpp,
+			width,
+			height,
+			gs_regs: 0,
+			gs_tex_clut: 0,
+			gs_tex_0: 0,
+			gs_tex_1: 0,
+			gs_miptbp_1: 0,
+			gs_miptbp_2: 0,
+			mipmap_sizes: Vec::new(),
+			user_data: UserData::default(),
+		})
+	}
+
+	pub(crate) fn read(buffer: &[u8], offset: &mut usize) -> Result<Header> {
+		let mut load_part = |size| { get_slice(buffer, offset, size) };
 
 		let mut result = Header {
-			total_size: LittleEndian::read_u32(load_part(4)),
-			clut_size: LittleEndian::read_u32(load_part(4)),
-			image_size: LittleEndian::read_u32(load_part(4)),
-			header_size: LittleEndian::read_u16(load_part(2)),
-			clut_color_count: LittleEndian::read_u16(load_part(2)),
-			picture_format: load_part(1)[0],
-			mipmap_count: load_part(1)[0],
-			clut_format: load_part(1)[0],
-			bpp: Self::find_bpp(load_part(1)[0])?,
-			width: LittleEndian::read_u16(load_part(2)),
-			height: LittleEndian::read_u16(load_part(2)),
-			gs_tex_0: LittleEndian::read_u64(load_part(8)),
-			gs_tex_1: LittleEndian::read_u64(load_part(8)),
-			gs_regs: LittleEndian::read_u32(load_part(4)),
-			gs_tex_clut: LittleEndian::read_u32(load_part(4)),
-			user_data: Vec::new(),
+			total_size: LittleEndian::read_u32(load_part(4)?),
+			clut_size: LittleEndian::read_u32(load_part(4)?),
+			image_size: LittleEndian::read_u32(load_part(4)?),
+			header_size: LittleEndian::read_u16(load_part(2)?),
+			clut_color_count: LittleEndian::read_u16(load_part(2)?),
+			picture_format: load_part(1)?[0],
+			mipmap_count: load_part(1)?[0],
+			clut_format: load_part(1)?[0],
+			bpp: Self::find_bpp(load_part(1)?[0])?,
+			width: LittleEndian::read_u16(load_part(2)?),
+			height: LittleEndian::read_u16(load_part(2)?),
+			gs_tex_0: LittleEndian::read_u64(load_part(8)?),
+			gs_tex_1: LittleEndian::read_u64(load_part(8)?),
+			gs_regs: LittleEndian::read_u32(load_part(4)?),
+			gs_tex_clut: LittleEndian::read_u32(load_part(4)?),
+			gs_miptbp_1: 0,
+			gs_miptbp_2: 0,
+			mipmap_sizes: Vec::new(),
+			user_data: UserData::default(),
 		};
 
-		let user_data_size = result.header_size as usize - 48;
+		if result.has_mipmaps() {
+			result.gs_miptbp_1 = LittleEndian::read_u64(load_part(8)?);
+			result.gs_miptbp_2 = LittleEndian::read_u64(load_part(8)?);
+
+			for _ in 0..result.mipmap_count {
+				result.mipmap_sizes.push(LittleEndian::read_u32(load_part(4)?));
+			}
+
+			load_part(result.mipmap_header_size() - 16 - result.mipmap_sizes.len() * 4)?;
+
+			// the levels are laid out back to back inside the image data
+			let sizes: u64 = result.mipmap_sizes.iter().map(|v| *v as u64).sum();
+
+			if sizes > result.image_size as u64 {
+				return Err(Error::MipmapSizesTooLarge(sizes, result.image_size));
+			}
+		}
+
+		let user_data_size = (result.header_size as usize)
+			.checked_sub(48 + result.mipmap_header_size())
+			.ok_or(Error::HeaderSizeTooSmall(result.header_size))?;
+
 		if user_data_size > 0 {
-			result.user_data = load_part(user_data_size).to_vec();
+			result.user_data = UserData::parse(load_part(user_data_size)?);
 		}
 
 		if result.is_paletted() && result.bpp > 8 {
@@ -79,7 +181,48 @@ impl Header {
 		}
 	}
 
-	fn find_bpp(v: u8) -> Result<u8> {
+	fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+		writer.write_u32::<LittleEndian>(self.total_size)?;
+		writer.write_u32::<LittleEndian>(self.clut_size)?;
+		writer.write_u32::<LittleEndian>(self.image_size)?;
+		writer.write_u16::<LittleEndian>(self.header_size)?;
+		writer.write_u16::<LittleEndian>(self.clut_color_count)?;
+		writer.write_u8(self.picture_format)?;
+		writer.write_u8(self.mipmap_count)?;
+		writer.write_u8(self.clut_format)?;
+		writer.write_u8(Self::find_bpp_format(self.bpp)?)?;
+		writer.write_u16::<LittleEndian>(self.width)?;
+		writer.write_u16::<LittleEndian>(self.height)?;
+		writer.write_u64::<LittleEndian>(self.gs_tex_0)?;
+		writer.write_u64::<LittleEndian>(self.gs_tex_1)?;
+		writer.write_u32::<LittleEndian>(self.gs_regs)?;
+		writer.write_u32::<LittleEndian>(self.gs_tex_clut)?;
+
+		if self.has_mipmaps() {
+			let padding = self.mipmap_header_size() - 16 - self.mipmap_sizes.len() * 4;
+
+			writer.write_u64::<LittleEndian>(self.gs_miptbp_1)?;
+			writer.write_u64::<LittleEndian>(self.gs_miptbp_2)?;
+
+			for size in &self.mipmap_sizes {
+				writer.write_u32::<LittleEndian>(*size)?;
+			}
+
+			writer.write_all(&vec![0u8; padding])?;
+		}
+
+		let user_data_size = self.user_data_size();
+
+		if self.user_data.len() > user_data_size {
+			return Err(Error::UserDataTooLarge(self.user_data.len(), user_data_size));
+		}
+
+		writer.write_all(&self.user_data.encode(user_data_size))?;
+
+		Ok(())
+	}
+
+	pub(crate) fn find_bpp(v: u8) -> Result<u8> {
 		match v {
 			1 => Ok(16),
 			2 => Ok(24),
@@ -90,10 +233,69 @@ impl Header {
 		}
 	}
 
+	fn find_bpp_format(bpp: u8) -> Result<u8> {
+		match bpp {
+			16 => Ok(1),
+			24 => Ok(2),
+			32 => Ok(3),
+			4 => Ok(4),
+			8 => Ok(5),
+			n => Err(Error::InvalidBpp(n)),
+		}
+	}
+
 	pub fn has_mipmaps(&self) -> bool {
 		self.mipmap_count > 1
 	}
 
+	/// Number of image levels stored in the frame, including the base level.
+	pub fn level_count(&self) -> usize {
+		(self.mipmap_count as usize).max(1)
+	}
+
+	/// Dimensions of a mipmap level, where level 0 is the full size image.
+	pub fn level_size(&self, level: usize) -> (u16, u16) {
+		let width = (self.width as u32).checked_shr(level as u32).unwrap_or(0).max(1);
+		let height = (self.height as u32).checked_shr(level as u32).unwrap_or(0).max(1);
+
+		(width as u16, height as u16)
+	}
+
+	/// Size of the header, image data and CLUT added up.
+	pub(crate) fn parts_size(&self) -> u64 {
+		self.header_size as u64 + self.image_size as u64 + self.clut_size as u64
+	}
+
+	/// Size the frame takes up in the file, as declared by `total_size`.
+	///
+	/// Some files leave the header out of `total_size`, so a declared size too small to
+	/// hold the parts of the frame falls back to their sum.
+	pub(crate) fn frame_size(&self) -> u64 {
+		(self.total_size as u64).max(self.parts_size())
+	}
+
+	fn level_image_size(&self, level: usize) -> usize {
+		if self.has_mipmaps() {
+			self.mipmap_sizes.get(level).map_or(0, |v| *v as usize)
+		} else {
+			self.image_size as usize
+		}
+	}
+
+	fn user_data_size(&self) -> usize {
+		(self.header_size as usize).saturating_sub(48 + self.mipmap_header_size())
+	}
+
+	fn mipmap_header_size(&self) -> usize {
+		if self.has_mipmaps() {
+			let size = 16 + self.mipmap_count as usize * 4;
+
+			(size + 15) & !15
+		} else {
+			0
+		}
+	}
+
 	pub fn is_paletted(&self) -> bool {
 		self.clut_size > 0
 	}
@@ -181,7 +383,49 @@ impl Header {
 		self.gs_tex_1
 	}
 
-	pub fn user_data(&self) -> &Vec<u8> {
+	pub fn gs_miptbp_1(&self) -> u64 {
+		self.gs_miptbp_1
+	}
+
+	pub fn gs_miptbp_2(&self) -> u64 {
+		self.gs_miptbp_2
+	}
+
+	pub fn mipmap_sizes(&self) -> &Vec<u32> {
+		&self.mipmap_sizes
+	}
+
+	/// Decodes the TEX0 register.
+	pub fn tex_0(&self) -> Tex0 {
+		Tex0::from_raw(self.gs_tex_0)
+	}
+
+	/// Decodes the TEX1 register.
+	pub fn tex_1(&self) -> Tex1 {
+		Tex1::from_raw(self.gs_tex_1)
+	}
+
+	/// Decodes the TEXCLUT register.
+	pub fn tex_clut(&self) -> TexClut {
+		TexClut::from_raw(self.gs_tex_clut)
+	}
+
+	/// Decodes the MIPTBP1 register, holding mipmap levels 1 to 3.
+	pub fn miptbp_1(&self) -> MipTbp {
+		MipTbp::from_raw(self.gs_miptbp_1)
+	}
+
+	/// Decodes the MIPTBP2 register, holding mipmap levels 4 to 6.
+	pub fn miptbp_2(&self) -> MipTbp {
+		MipTbp::from_raw(self.gs_miptbp_2)
+	}
+
+	/// Works out how the texels are ordered from TEX0 and the image size.
+	pub fn storage(&self) -> Storage {
+		Storage::detect(&self.tex_0(), self.bpp, self.width, self.height)
+	}
+
+	pub fn user_data(&self) -> &UserData {
 		&self.user_data
 	}
 }
@@ -190,35 +434,330 @@ impl Header {
 pub struct Frame {
 	header: Header,
 	data: DataKind,
+	mipmaps: Vec<DataKind>,
 	palettes: Vec<PixelBuffer>,
 }
 
 impl Frame {
+	/// Builds a paletted frame from one palette index per pixel.
+	///
+	/// Palettes are given in linear order and padded with transparent black up to the
+	/// 16 or 256 colors of `format`; the CSM1 ordering and swizzling are applied on write.
+	pub fn from_indexed(
+		width: u16,
+		height: u16,
+		format: Format,
+		indices: &[u8],
+		palettes: Vec<PixelBuffer>,
+		clut: ClutFormat,
+	) -> Result<Frame> {
+		let color_count = match format {
+			Format::Indexed4 => 16,
+			Format::Indexed8 => 256,
+			n => return Err(Error::UnsupportedFormat(n)),
+		};
+
+		Frame::check_len(width, height, indices.len())?;
+
+		if palettes.is_empty() {
+			return Err(Error::InvalidPaletteCount(0));
+		}
+
+		if let Some(index) = indices.iter().find(|v| **v as usize >= color_count) {
+			return Err(Error::IndexOutOfRange(*index));
+		}
+
+		let mut palettes = palettes;
+
+		for palette in &mut palettes {
+			if palette.len() > color_count {
+				return Err(Error::TooManyColors(palette.len()));
+			}
+
+			palette.resize(color_count, Pixel::rgba(0, 0, 0, 0));
+		}
+
+		Ok(Frame {
+			header: Header::new(width, height, format, clut, &palettes)?,
+			data: DataKind::Indices(indices.to_vec()),
+			mipmaps: Vec::new(),
+			palettes,
+		})
+	}
+
+	/// Builds a frame from an RGBA8 buffer.
+	///
+	/// True color formats store the pixels directly. Indexed formats build a palette from
+	/// the distinct colors of the buffer, which must fit in 16 or 256 entries. The error
+	/// comes at the first color that doesn't fit, holding the count up to it.
+	pub fn from_rgba(width: u16, height: u16, format: Format, rgba: &[u8]) -> Result<Frame> {
+		let expected = width as usize * height as usize * 4;
+
+		if rgba.len() != expected {
+			return Err(Error::InvalidBufferSize(expected, rgba.len()));
+		}
+
+		let pixels: PixelBuffer = rgba
+			.chunks(4)
+			.map(|v| Pixel::rgba(v[0], v[1], v[2], v[3]))
+			.collect();
+
+		if !format.is_indexed() {
+			return Ok(Frame {
+				header: Header::new(width, height, format, ClutFormat::default(), &[])?,
+				data: DataKind::Pixels(pixels),
+				mipmaps: Vec::new(),
+				palettes: Vec::new(),
+			});
+		}
+
+		let color_count = 1usize << format.bpp();
+		let mut palette = Vec::new();
+		let mut lookup: HashMap<Pixel, u8> = HashMap::new();
+		let mut indices = Vec::with_capacity(pixels.len());
+
+		for pixel in pixels {
+			let index = match lookup.get(&pixel) {
+				Some(i) => *i,
+				None if palette.len() == color_count => return Err(Error::TooManyColors(color_count + 1)),
+				None => {
+					let index = palette.len() as u8;
+
+					lookup.insert(pixel, index);
+					palette.push(pixel);
+					index
+				},
+			};
+
+			indices.push(index);
+		}
+
+		Frame::from_indexed(width, height, format, &indices, vec![palette], ClutFormat::default())
+	}
+
+	/// Builds a paletted frame from an RGBA8 buffer with any number of colors.
+	///
+	/// The buffer is reduced to the 16 or 256 colors of `format` with [`quantize`].
+	pub fn from_rgba_quantized(
+		width: u16,
+		height: u16,
+		format: Format,
+		rgba: &[u8],
+		options: QuantizeOptions,
+	) -> Result<Frame> {
+		if !format.is_indexed() {
+			return Err(Error::UnsupportedFormat(format));
+		}
+
+		let result = quantize(width, height, rgba, 1 << format.bpp(), options)?;
+
+		Frame::from_indexed(width, height, format, &result.indices, vec![result.palette], ClutFormat::default())
+	}
+
+	/// Builds a frame from an RGBA8 buffer laid out exactly like `template`.
+	///
+	/// The header is kept as it is, so the pixel format, CLUT format, storage mode, GS
+	/// registers and user data all carry over, and mipmaps are point sampled from the new
+	/// image. Paletted frames keep every palette entry the image still uses, and new colors
+	/// take the entries of the first palette that it no longer uses.
+	///
+	/// Pixels with the RGB of `color_key` and no alpha, the way [`Frame::to_raw`] writes
+	/// them, map back to the color key. Other transparent pixels fall back to any
+	/// transparent palette entry.
+	///
+	/// ```
+	/// use tim2::Frame;
+	///
+	/// let image = tim2::load("../assets/test.tm2").unwrap();
+	/// let template = image.get_frame(0);
+	/// let (width, height) = (template.header().width(), template.header().height());
+	/// let raw = template.to_raw(Some(tim2::COLOR_KEY));
+	/// let frame = Frame::from_template(template, width, height, &raw, Some(tim2::COLOR_KEY)).unwrap();
+	///
+	/// assert_eq!(frame.to_raw(Some(tim2::COLOR_KEY)), raw);
+	/// ```
+	pub fn from_template(template: &Frame, width: u16, height: u16, rgba: &[u8], color_key: Option<Pixel>) -> Result<Frame> {
+		let header = &template.header;
+
+		if (width, height) != (header.width, header.height) {
+			return Err(Error::InvalidDimensions(width as u32, height as u32));
+		}
+
+		Frame::check_len(width, height, rgba.len() / 4)?;
+
+		let pixels: PixelBuffer = rgba.chunks_exact(4).map(|v| Pixel::rgba(v[0], v[1], v[2], v[3])).collect();
+		let mut palettes = template.palettes.clone();
+		let data = match &template.data {
+			DataKind::Indices(original) => {
+				let palette = palettes.first_mut().ok_or(Error::InvalidPaletteCount(0))?;
+
+				DataKind::Indices(Frame::map_to_palette(&pixels, original, palette, color_key)?)
+			},
+			DataKind::Pixels(_) => {
+				let pixels = pixels.iter().map(|v| Frame::restore_color_key(*v, color_key)).collect();
+
+				DataKind::Pixels(pixels)
+			},
+		};
+
+		let mipmaps = (1..header.level_count())
+			.map(|level| Frame::sample_level(&data, header, level))
+			.collect();
+
+		Ok(Frame {
+			header: header.clone(),
+			data,
+			mipmaps,
+			palettes,
+		})
+	}
+
+	/// Turns a pixel `to_raw` made transparent for matching `color_key` back into the key.
+	fn restore_color_key(pixel: Pixel, color_key: Option<Pixel>) -> Pixel {
+		match color_key {
+			Some(key) if pixel.a() == 0 && (pixel.r(), pixel.g(), pixel.b()) == (key.r(), key.g(), key.b()) => key,
+			_ => pixel,
+		}
+	}
+
+	/// Finds the palette index of every pixel, adding colors missing from the palette in
+	/// place of the entries no pixel uses. Pixels keep their `original` index when it still
+	/// holds their color, so palettes with repeated colors come back unchanged.
+	fn map_to_palette(pixels: &[Pixel], original: &[u8], palette: &mut PixelBuffer, color_key: Option<Pixel>) -> Result<Vec<u8>> {
+		let mut used = vec![false; palette.len()];
+		let mut missing = Vec::new();
+		let find = |palette: &[Pixel], i: usize, pixel: Pixel| {
+			let hint = original.get(i).map(|v| *v as usize).filter(|v| {
+				palette.get(*v).is_some_and(|v| *v == pixel || *v == Frame::restore_color_key(pixel, color_key))
+			});
+
+			hint.or_else(|| Frame::find_color(palette, pixel, color_key))
+		};
+
+		for (i, pixel) in pixels.iter().enumerate() {
+			match find(palette, i, *pixel) {
+				Some(i) => used[i] = true,
+				None => {
+					let color = Frame::restore_color_key(*pixel, color_key);
+
+					if !missing.contains(&color) {
+						missing.push(color);
+					}
+				},
+			}
+		}
+
+		let free: Vec<usize> = (0..palette.len()).filter(|i| !used[*i]).collect();
+
+		if missing.len() > free.len() {
+			let color_count = used.iter().filter(|v| **v).count() + missing.len();
+
+			return Err(Error::TooManyColors(color_count));
+		}
+
+		for (color, index) in missing.iter().zip(free) {
+			palette[index] = *color;
+		}
+
+		let result = pixels
+			.iter()
+			.enumerate()
+			.map(|(i, pixel)| find(palette, i, *pixel).unwrap_or(0) as u8)
+			.collect();
+
+		Ok(result)
+	}
+
+	/// Looks a pixel up by its exact color, then as the color key, then as any transparent entry.
+	fn find_color(palette: &[Pixel], pixel: Pixel, color_key: Option<Pixel>) -> Option<usize> {
+		let keyed = Frame::restore_color_key(pixel, color_key);
+
+		palette
+			.iter()
+			.position(|v| *v == pixel)
+			.or_else(|| palette.iter().position(|v| *v == keyed))
+			.or_else(|| palette.iter().position(|v| pixel.a() == 0 && v.a() == 0))
+	}
+
+	/// Builds a mipmap level by taking the top left texel of each block of the full size image.
+	fn sample_level(data: &DataKind, header: &Header, level: usize) -> DataKind {
+		let (width, height) = header.level_size(level);
+		let positions = (0..height as usize).flat_map(|y| (0..width as usize).map(move |x| (x, y)));
+		let base_width = header.width as usize;
+		let offset = |(x, y): (usize, usize)| (y << level) * base_width + (x << level);
+
+		match data {
+			DataKind::Indices(v) => DataKind::Indices(positions.map(|p| v[offset(p)]).collect()),
+			DataKind::Pixels(v) => DataKind::Pixels(positions.map(|p| v[offset(p)]).collect()),
+		}
+	}
+
+	fn check_len(width: u16, height: u16, len: usize) -> Result<()> {
+		let expected = width as usize * height as usize;
+
+		if len != expected {
+			Err(Error::InvalidBufferSize(expected, len))
+		} else {
+			Ok(())
+		}
+	}
+
 	pub fn read(buffer: &[u8], offset: &mut usize) -> Result<Frame> {
 		let header = Header::read(buffer, offset)?;
 
-		println!("{:#?}", &header);
+		debug!("{:#?}", &header);
 
-		let data = Frame::read_data(buffer, offset, &header)?;
-		let palettes= Frame::read_palettes(buffer, offset, &header)?;
+		let mut mipmaps = Frame::read_data(buffer, offset, &header)?;
+		let data = mipmaps.remove(0);
+		let palettes = Frame::read_palettes(buffer, offset, &header)?;
 
 		Ok(Frame {
 			header,
 			data,
+			mipmaps,
 			palettes,
 		})
 	}
 
-	fn read_data(buffer: &[u8], offset: &mut usize, header: &Header) -> Result<DataKind> {
+	/// Serializes the frame, including its header, pixel data and CLUT.
+	pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+		self.header.write(writer)?;
+		self.write_data(writer)?;
+		self.write_palettes(writer)
+	}
+
+	fn read_data(buffer: &[u8], offset: &mut usize, header: &Header) -> Result<Vec<DataKind>> {
+		let mut end = *offset;
+		let mut result = Vec::with_capacity(header.level_count());
+
+		get_slice(buffer, &mut end, header.image_size as usize)?;
+
+		for level in 0..header.level_count() {
+			let slice = get_slice(buffer, offset, header.level_image_size(level))?;
+
+			result.push(Frame::read_level(slice, header, level)?);
+		}
+
+		*offset = end;
+		Ok(result)
+	}
+
+	fn read_level(slice: &[u8], header: &Header, level: usize) -> Result<DataKind> {
+		let (width, height) = header.level_size(level);
 		let pixel_size = header.bpp as usize / 8;
-		let size = header.image_size as usize;
-		let slice = get_slice(buffer, offset, size);
+		let expected = width as usize * height as usize * header.bpp as usize / 8;
+
+		if slice.len() < expected {
+			return Err(Error::InvalidBufferSize(expected, slice.len()));
+		}
+
 		let data = if header.bpp == 4 {
 			let mut result = Vec::with_capacity(slice.len() * 2);
 
+			// the low nibble holds the leftmost pixel
 			for index_pair in slice {
-				result.push(*index_pair & 0xF0 >> 4);
-				result.push(*index_pair & 0xF);
+				result.push(*index_pair & 0x0F);
+				result.push(*index_pair >> 4);
 			}
 
 			result
@@ -226,13 +765,15 @@ impl Frame {
 			slice.to_vec()
 		};
 
+		let storage = header.storage();
+
 		if header.clut_size > 0 {
-			let raw = Frame::unswizzle(&data.to_vec(), header);
+			let raw = storage.decode(&data, width, height, header.bpp, 0);
 
 			Ok(DataKind::Indices(raw))
 		} else {
 			let colors = Frame::read_colors(&data, pixel_size)?;
-			let raw = Frame::unswizzle(&colors, header);
+			let raw = storage.decode(&colors, width, height, header.bpp, Pixel::rgba(0, 0, 0, 0));
 
 			Ok(DataKind::Pixels(raw))
 		}
@@ -244,16 +785,22 @@ impl Frame {
 		}
 
 		let total_size = header.clut_size as usize;
-		let slice = get_slice(buffer, offset, total_size);
-		let size = (header.clut_color_count * header.color_size() as u16) as usize;
-		let count = total_size / size;
+		let slice = get_slice(buffer, offset, total_size)?;
 		let color_size = header.color_size() as usize;
+		let size = header.clut_color_count as usize * color_size;
+
+		if size == 0 {
+			return Err(Error::ZeroClutColorCount);
+		}
+
+		let count = total_size / size;
 		let mut result = Vec::with_capacity(count);
 
-		for i in 0..count {
-			let start_index = size * i;
-			let end_index = start_index + size;
-			let data = &slice[start_index..end_index];
+		if count == 0 {
+			return Err(Error::InvalidClutSize(header.clut_size));
+		}
+
+		for data in slice.chunks_exact(size) {
 			let mut palette = Frame::read_colors(data, color_size)?;
 
 			if !header.is_linear_palette() && header.bpp == 8 {
@@ -266,12 +813,85 @@ impl Frame {
 		Ok(result)
 	}
 
+	fn write_data<W: Write>(&self, writer: &mut W) -> Result<()> {
+		let levels = std::iter::once(&self.data).chain(self.mipmaps.iter());
+		let mut written = 0;
+
+		for (level, data) in levels.enumerate() {
+			self.write_level(writer, data, level)?;
+			written += self.header.level_image_size(level);
+		}
+
+		let padding = (self.header.image_size as usize).saturating_sub(written);
+
+		writer.write_all(&vec![0u8; padding])?;
+
+		Ok(())
+	}
+
+	fn write_level<W: Write>(&self, writer: &mut W, data: &DataKind, level: usize) -> Result<()> {
+		let header = &self.header;
+		let (width, height) = header.level_size(level);
+		let storage = header.storage();
+		let mut raw = match data {
+			DataKind::Indices(v) => {
+				let raw = storage.encode(v, width, height, header.bpp, 0);
+
+				if header.bpp == 4 {
+					raw.chunks(2)
+						.map(|pair| (pair.get(1).unwrap_or(&0) << 4) | (pair[0] & 0xF))
+						.collect()
+				} else {
+					raw
+				}
+			},
+			DataKind::Pixels(v) => {
+				let raw = storage.encode(v, width, height, header.bpp, Pixel::rgba(0, 0, 0, 0));
+
+				Frame::write_colors(&raw, header.bpp as usize / 8)?
+			},
+		};
+
+		raw.resize(header.level_image_size(level), 0);
+		writer.write_all(&raw)?;
+
+		Ok(())
+	}
+
+	fn write_palettes<W: Write>(&self, writer: &mut W) -> Result<()> {
+		let header = &self.header;
+
+		if header.clut_size == 0 {
+			return Ok(());
+		}
+
+		let mut data = Vec::with_capacity(header.clut_size as usize);
+
+		for palette in &self.palettes {
+			let mut palette = palette.clone();
+
+			// the CSM1 reordering is its own inverse
+			if !header.is_linear_palette() && header.bpp == 8 {
+				Frame::linearize_palette(&mut palette);
+			}
+
+			data.append(&mut Frame::write_colors(&palette, header.color_size() as usize)?);
+		}
+
+		data.resize(header.clut_size as usize, 0);
+		writer.write_all(&data)?;
+
+		Ok(())
+	}
+
 	fn read_colors(buffer: &[u8], color_size: usize) -> Result<PixelBuffer> {
-		let mut offset = 0usize;
-		let mut result = Vec::new();
+		if color_size == 0 {
+			return Err(Error::InvalidPixelSize(color_size));
+		}
 
-		for _ in (0..buffer.len()).step_by(color_size) {
-			let slice = get_slice(buffer, &mut offset, color_size);
+		let mut result = Vec::with_capacity(buffer.len() / color_size);
+
+		for slice in buffer.chunks_exact(color_size) {
 			let pixel = Pixel::from_buf(slice)?;
 
 			result.push(pixel)
@@ -280,6 +900,16 @@ impl Frame {
 		Ok(result)
 	}
 
+	fn write_colors(colors: &[Pixel], color_size: usize) -> Result<Vec<u8>> {
+		let mut result = Vec::with_capacity(colors.len() * color_size);
+
+		for pixel in colors {
+			result.append(&mut pixel.to_buf(color_size)?);
+		}
+
+		Ok(result)
+	}
+
 	fn linearize_palette(palette: &mut PixelBuffer) {
 		const COLOR_COUNT: usize = 8;
 		const BLOCK_COUNT: usize = 2;
@@ -305,66 +935,171 @@ impl Frame {
 		}
 	}
 
-	fn unswizzle<T: Default + Copy>(buffer: &Vec<T>, header: &Header) -> Vec<T> {
-		let mut i = 0usize;
-		let mut result = vec![Default::default(); buffer.len()];
-		let width = header.width as usize;
-		let height = header.height as usize;
-
-		for y in (0..height).step_by(SWIZZLE_HEIGHT) {
-			for x in (0..width).step_by(SWIZZLE_WIDTH) {
-				for tile_y in y..(y + SWIZZLE_HEIGHT) {
-					for tile_x in x..(x + SWIZZLE_WIDTH) {
-						if tile_x < width && tile_y < height {
-							let index = tile_y * width + tile_x;
-
-							if let Some(value) = buffer.get(i) {
-								result[index] = *value;
-							}
-						}
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
 
-						i += 1;
-					}
-				}
-			}
+	pub fn data(&self) -> &DataKind {
+		&self.data
+	}
+
+	/// Returns every palette stored in the CLUT, in linear order.
+	pub fn palettes(&self) -> &Vec<PixelBuffer> {
+		&self.palettes
+	}
+
+	/// Replaces the user data, resizing the header to fit it in steps of 16 bytes.
+	pub fn set_user_data(&mut self, user_data: UserData) -> Result<()> {
+		let header = &mut self.header;
+		let fixed_size = 48 + header.mipmap_header_size();
+		let header_size = (fixed_size + user_data.len()).next_multiple_of(16);
+
+		if header_size > u16::MAX as usize {
+			return Err(Error::UserDataTooLarge(user_data.len(), (u16::MAX as usize & !15) - fixed_size));
 		}
 
-		result
+		let total_size = (header.total_size as u64 + header_size as u64).saturating_sub(header.header_size as u64);
+
+		header.total_size = total_size as u32;
+		header.header_size = header_size as u16;
+		header.user_data = user_data;
+
+		Ok(())
 	}
 
-	pub fn header(&self) -> &Header {
-		&self.header
+	/// Replaces the palette at `index`, padding it with transparent black to the CLUT size.
+	pub fn set_palette(&mut self, index: usize, palette: PixelBuffer) -> Result<()> {
+		let color_count = self.header.clut_color_count as usize;
+		let target = self.palettes.get_mut(index).ok_or(Error::InvalidPaletteIndex(index))?;
+
+		if palette.len() > color_count {
+			return Err(Error::TooManyColors(palette.len()));
+		}
+
+		*target = palette;
+		target.resize(color_count, Pixel::rgba(0, 0, 0, 0));
+
+		Ok(())
 	}
 
-	pub fn data(&self) -> &DataKind {
-		&self.data
+	/// Replaces the colors of the palette at `index` but keeps their alpha, for colors
+	/// imported from a format without alpha.
+	pub fn recolor_palette(&mut self, index: usize, colors: &[Pixel]) -> Result<()> {
+		let target = self.palettes.get_mut(index).ok_or(Error::InvalidPaletteIndex(index))?;
+
+		if colors.len() > target.len() {
+			return Err(Error::TooManyColors(colors.len()));
+		}
+
+		for (entry, color) in target.iter_mut().zip(colors) {
+			*entry = Pixel::rgba(color.r(), color.g(), color.b(), entry.a());
+		}
+
+		Ok(())
+	}
+
+	/// Returns the pixels of every image level, starting with the full size image.
+	///
+	/// Level `n` is `header().level_size(n)` pixels in size.
+	pub fn mip_levels(&self) -> Vec<PixelBuffer> {
+		let levels = std::iter::once(&self.data).chain(self.mipmaps.iter());
+
+		levels.map(|v| self.resolve_pixels(v)).collect()
 	}
 
 	pub fn get_pixels(&self) -> PixelBuffer {
+		self.resolve_pixels(&self.data)
+	}
+
+	/// Returns the pixels of the full size image using the palette at `index`.
+	///
+	/// True color frames have no palettes and ignore `index`.
+	pub fn get_pixels_with_palette(&self, index: usize) -> Result<PixelBuffer> {
 		match &self.data {
 			DataKind::Indices(v) => {
-				let palette = &self.palettes[0];
-				let mut result = Vec::with_capacity(v.len());
+				let palette = self.palettes.get(index).ok_or(Error::InvalidPaletteIndex(index))?;
 
-				for index in v {
-					result.push(palette[*index as usize]);
-				}
-
-				result
+				Ok(Frame::lookup(v, palette))
 			},
+			DataKind::Pixels(v) => Ok(v.to_vec()),
+		}
+	}
+
+	fn resolve_pixels(&self, data: &DataKind) -> PixelBuffer {
+		match data {
+			DataKind::Indices(v) => Frame::lookup(v, self.palettes.first().map_or(&[], |v| v)),
 			DataKind::Pixels(v) => v.to_vec(),
 		}
 	}
 
+	/// Resolves palette indices, mapping indices past the end of the palette to transparent black.
+	fn lookup(indices: &[u8], palette: &[Pixel]) -> PixelBuffer {
+		let mut result = Vec::with_capacity(indices.len());
+
+		for index in indices {
+			let pixel = palette.get(*index as usize).copied();
+
+			result.push(pixel.unwrap_or(Pixel::rgba(0, 0, 0, 0)));
+		}
+
+		result
+	}
+
 	pub fn to_raw(&self, color_key: Option<Pixel>) -> Vec<u8> {
-		let pixels = self.get_pixels();
+		self.to_raw_with_options(color_key, DecodeOptions::default())
+	}
+
+	/// Converts the full size image to RGBA8, mapping alpha as `options` asks.
+	///
+	/// ```
+	/// use tim2::{AlphaMode, DecodeOptions, Format, Frame};
+	///
+	/// let frame = Frame::from_rgba(1, 1, Format::Rgba8888, &[255, 0, 0, 0x40]).unwrap();
+	/// let options = DecodeOptions { alpha: AlphaMode::Ps2Scaled };
+	///
+	/// assert_eq!(frame.to_raw_with_options(None, options), vec![255, 0, 0, 0x80]);
+	/// ```
+	pub fn to_raw_with_options(&self, color_key: Option<Pixel>, options: DecodeOptions) -> Vec<u8> {
+		Frame::pixels_to_raw(self.get_pixels(), color_key, options)
+	}
+
+	/// Converts the full size image to RGBA8 using the palette at `index`.
+	pub fn to_raw_with_palette(&self, index: usize, color_key: Option<Pixel>) -> Result<Vec<u8>> {
+		self.to_raw_with_palette_and_options(index, color_key, DecodeOptions::default())
+	}
+
+	/// Converts the full size image to RGBA8 using the palette at `index`, mapping alpha as
+	/// `options` asks.
+	pub fn to_raw_with_palette_and_options(&self, index: usize, color_key: Option<Pixel>, options: DecodeOptions) -> Result<Vec<u8>> {
+		let pixels = self.get_pixels_with_palette(index)?;
+
+		Ok(Frame::pixels_to_raw(pixels, color_key, options))
+	}
+
+	/// Converts one image level to RGBA8, or returns `None` if the frame has no such level.
+	pub fn mip_level_to_raw(&self, level: usize, color_key: Option<Pixel>) -> Option<Vec<u8>> {
+		self.mip_level_to_raw_with_options(level, color_key, DecodeOptions::default())
+	}
+
+	/// Converts one image level to RGBA8, like [`Frame::to_raw_with_options`] does for the
+	/// full size image.
+	pub fn mip_level_to_raw_with_options(&self, level: usize, color_key: Option<Pixel>, options: DecodeOptions) -> Option<Vec<u8>> {
+		let data = match level {
+			0 => &self.data,
+			n => self.mipmaps.get(n - 1)?,
+		};
+
+		Some(Frame::pixels_to_raw(self.resolve_pixels(data), color_key, options))
+	}
+
+	fn pixels_to_raw(pixels: PixelBuffer, color_key: Option<Pixel>, options: DecodeOptions) -> Vec<u8> {
 		let mut result = Vec::with_capacity(pixels.len() * 4);
 
 		for pixel in pixels {
 			let alpha = if let Some(v) = color_key {
-				if v != pixel { pixel.a() } else { 0 }
+				if v != pixel { options.alpha.apply(pixel.a()) } else { 0 }
 			} else {
-				pixel.a()
+				options.alpha.apply(pixel.a())
 			};
 
 			result.push(pixel.r());
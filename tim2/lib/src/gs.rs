@@ -0,0 +1,336 @@
+fn bits(raw: u64, start: u32, count: u32) -> u64 {
+	(raw >> start) & ((1 << count) - 1)
+}
+
+fn set_bits(raw: &mut u64, start: u32, count: u32, value: u64) {
+	let mask = ((1 << count) - 1) << start;
+
+	*raw = (*raw & !mask) | ((value << start) & mask);
+}
+
+/// Pixel storage mode (PSM/CPSM).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Psm {
+	Psmct32,
+	Psmct24,
+	Psmct16,
+	Psmct16s,
+	Psmt8,
+	Psmt4,
+	Psmt8h,
+	Psmt4hl,
+	Psmt4hh,
+	Other(u8),
+}
+
+impl Psm {
+	pub fn from_raw(v: u8) -> Psm {
+		match v {
+			0x00 => Psm::Psmct32,
+			0x01 => Psm::Psmct24,
+			0x02 => Psm::Psmct16,
+			0x0A => Psm::Psmct16s,
+			0x13 => Psm::Psmt8,
+			0x14 => Psm::Psmt4,
+			0x1B => Psm::Psmt8h,
+			0x24 => Psm::Psmt4hl,
+			0x2C => Psm::Psmt4hh,
+			n => Psm::Other(n),
+		}
+	}
+
+	pub fn to_raw(self) -> u8 {
+		match self {
+			Psm::Psmct32 => 0x00,
+			Psm::Psmct24 => 0x01,
+			Psm::Psmct16 => 0x02,
+			Psm::Psmct16s => 0x0A,
+			Psm::Psmt8 => 0x13,
+			Psm::Psmt4 => 0x14,
+			Psm::Psmt8h => 0x1B,
+			Psm::Psmt4hl => 0x24,
+			Psm::Psmt4hh => 0x2C,
+			Psm::Other(n) => n,
+		}
+	}
+}
+
+/// Texture function (TFX), how texels are combined with the vertex color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextureFunction {
+	Modulate,
+	Decal,
+	Highlight,
+	Highlight2,
+}
+
+impl TextureFunction {
+	fn from_raw(v: u8) -> TextureFunction {
+		match v & 0x03 {
+			0 => TextureFunction::Modulate,
+			1 => TextureFunction::Decal,
+			2 => TextureFunction::Highlight,
+			_ => TextureFunction::Highlight2,
+		}
+	}
+
+	fn to_raw(self) -> u8 {
+		match self {
+			TextureFunction::Modulate => 0,
+			TextureFunction::Decal => 1,
+			TextureFunction::Highlight => 2,
+			TextureFunction::Highlight2 => 3,
+		}
+	}
+}
+
+/// CLUT storage mode (CSM).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Csm {
+	Csm1,
+	Csm2,
+}
+
+/// Texture filter used for magnification (MMAG) and minification (MMIN).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Filter {
+	Nearest,
+	Linear,
+	NearestMipmapNearest,
+	NearestMipmapLinear,
+	LinearMipmapNearest,
+	LinearMipmapLinear,
+	Other(u8),
+}
+
+impl Filter {
+	fn from_raw(v: u8) -> Filter {
+		match v {
+			0 => Filter::Nearest,
+			1 => Filter::Linear,
+			2 => Filter::NearestMipmapNearest,
+			3 => Filter::NearestMipmapLinear,
+			4 => Filter::LinearMipmapNearest,
+			5 => Filter::LinearMipmapLinear,
+			n => Filter::Other(n),
+		}
+	}
+
+	fn to_raw(self) -> u8 {
+		match self {
+			Filter::Nearest => 0,
+			Filter::Linear => 1,
+			Filter::NearestMipmapNearest => 2,
+			Filter::NearestMipmapLinear => 3,
+			Filter::LinearMipmapNearest => 4,
+			Filter::LinearMipmapLinear => 5,
+			Filter::Other(n) => n,
+		}
+	}
+}
+
+/// Fields of the TEX0 register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tex0 {
+	/// Texture base pointer, in 64 word units.
+	pub tbp0: u16,
+
+	/// Texture buffer width, in 64 texel units.
+	pub tbw: u8,
+	pub psm: Psm,
+
+	/// Texture width as a power of two.
+	pub tw: u8,
+
+	/// Texture height as a power of two.
+	pub th: u8,
+
+	/// Uses the texture alpha when set, otherwise only RGB.
+	pub tcc: bool,
+	pub tfx: TextureFunction,
+
+	/// CLUT base pointer, in 64 word units.
+	pub cbp: u16,
+	pub cpsm: Psm,
+	pub csm: Csm,
+
+	/// CLUT entry offset, in 16 color units.
+	pub csa: u8,
+
+	/// CLUT buffer load control.
+	pub cld: u8,
+}
+
+impl Tex0 {
+	pub fn from_raw(raw: u64) -> Tex0 {
+		Tex0 {
+			tbp0: bits(raw, 0, 14) as u16,
+			tbw: bits(raw, 14, 6) as u8,
+			psm: Psm::from_raw(bits(raw, 20, 6) as u8),
+			tw: bits(raw, 26, 4) as u8,
+			th: bits(raw, 30, 4) as u8,
+			tcc: bits(raw, 34, 1) != 0,
+			tfx: TextureFunction::from_raw(bits(raw, 35, 2) as u8),
+			cbp: bits(raw, 37, 14) as u16,
+			cpsm: Psm::from_raw(bits(raw, 51, 4) as u8),
+			csm: if bits(raw, 55, 1) == 0 { Csm::Csm1 } else { Csm::Csm2 },
+			csa: bits(raw, 56, 5) as u8,
+			cld: bits(raw, 61, 3) as u8,
+		}
+	}
+
+	pub fn to_raw(&self) -> u64 {
+		let mut raw = 0;
+
+		set_bits(&mut raw, 0, 14, self.tbp0 as u64);
+		set_bits(&mut raw, 14, 6, self.tbw as u64);
+		set_bits(&mut raw, 20, 6, self.psm.to_raw() as u64);
+		set_bits(&mut raw, 26, 4, self.tw as u64);
+		set_bits(&mut raw, 30, 4, self.th as u64);
+		set_bits(&mut raw, 34, 1, self.tcc as u64);
+		set_bits(&mut raw, 35, 2, self.tfx.to_raw() as u64);
+		set_bits(&mut raw, 37, 14, self.cbp as u64);
+		set_bits(&mut raw, 51, 4, self.cpsm.to_raw() as u64);
+		set_bits(&mut raw, 55, 1, (self.csm == Csm::Csm2) as u64);
+		set_bits(&mut raw, 56, 5, self.csa as u64);
+		set_bits(&mut raw, 61, 3, self.cld as u64);
+
+		raw
+	}
+
+	/// Texture width in texels.
+	pub fn width(&self) -> u32 {
+		1 << self.tw.min(31)
+	}
+
+	/// Texture height in texels.
+	pub fn height(&self) -> u32 {
+		1 << self.th.min(31)
+	}
+}
+
+/// Fields of the TEX1 register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tex1 {
+	/// Computes the LOD from the K value alone when set.
+	pub lcm: bool,
+
+	/// Maximum mipmap level.
+	pub mxl: u8,
+	pub mmag: Filter,
+	pub mmin: Filter,
+
+	/// Derives the mipmap base pointers from TBP0 when set.
+	pub mtba: bool,
+
+	/// LOD parameter L.
+	pub l: u8,
+
+	/// LOD parameter K, signed fixed point with 4 fractional bits.
+	pub k: i16,
+}
+
+impl Tex1 {
+	pub fn from_raw(raw: u64) -> Tex1 {
+		let k = bits(raw, 32, 12) as i16;
+
+		Tex1 {
+			lcm: bits(raw, 0, 1) != 0,
+			mxl: bits(raw, 2, 3) as u8,
+			mmag: Filter::from_raw(bits(raw, 5, 1) as u8),
+			mmin: Filter::from_raw(bits(raw, 6, 3) as u8),
+			mtba: bits(raw, 9, 1) != 0,
+			l: bits(raw, 19, 2) as u8,
+			k: (k << 4) >> 4,
+		}
+	}
+
+	pub fn to_raw(&self) -> u64 {
+		let mut raw = 0;
+
+		set_bits(&mut raw, 0, 1, self.lcm as u64);
+		set_bits(&mut raw, 2, 3, self.mxl as u64);
+		set_bits(&mut raw, 5, 1, self.mmag.to_raw() as u64);
+		set_bits(&mut raw, 6, 3, self.mmin.to_raw() as u64);
+		set_bits(&mut raw, 9, 1, self.mtba as u64);
+		set_bits(&mut raw, 19, 2, self.l as u64);
+		set_bits(&mut raw, 32, 12, self.k as u16 as u64);
+
+		raw
+	}
+
+	/// LOD parameter K as a float.
+	pub fn k_value(&self) -> f32 {
+		self.k as f32 / 16.0
+	}
+}
+
+/// Fields of the TEXCLUT register, used to place CSM2 palettes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TexClut {
+	/// CLUT buffer width, in 64 texel units.
+	pub cbw: u8,
+
+	/// CLUT offset U, in 16 texel units.
+	pub cou: u8,
+
+	/// CLUT offset V.
+	pub cov: u16,
+}
+
+impl TexClut {
+	pub fn from_raw(raw: u32) -> TexClut {
+		let raw = raw as u64;
+
+		TexClut {
+			cbw: bits(raw, 0, 6) as u8,
+			cou: bits(raw, 6, 6) as u8,
+			cov: bits(raw, 12, 10) as u16,
+		}
+	}
+
+	pub fn to_raw(&self) -> u32 {
+		let mut raw = 0;
+
+		set_bits(&mut raw, 0, 6, self.cbw as u64);
+		set_bits(&mut raw, 6, 6, self.cou as u64);
+		set_bits(&mut raw, 12, 10, self.cov as u64);
+
+		raw as u32
+	}
+}
+
+/// Fields of the MIPTBP1 or MIPTBP2 register: base pointers and widths of three mipmap levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MipTbp {
+	pub tbp: [u16; 3],
+	pub tbw: [u8; 3],
+}
+
+impl MipTbp {
+	pub fn from_raw(raw: u64) -> MipTbp {
+		let mut result = MipTbp { tbp: [0; 3], tbw: [0; 3] };
+
+		for i in 0..3 {
+			let start = i as u32 * 20;
+
+			result.tbp[i] = bits(raw, start, 14) as u16;
+			result.tbw[i] = bits(raw, start + 14, 6) as u8;
+		}
+
+		result
+	}
+
+	pub fn to_raw(&self) -> u64 {
+		let mut raw = 0;
+
+		for i in 0..3 {
+			let start = i as u32 * 20;
+
+			set_bits(&mut raw, start, 14, self.tbp[i] as u64);
+			set_bits(&mut raw, start + 14, 6, self.tbw[i] as u64);
+		}
+
+		raw
+	}
+}
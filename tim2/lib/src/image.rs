@@ -2,29 +2,33 @@ use crate::common::*;
 use crate::error::Error;
 use crate::frame::Frame;
 
-use byteorder::{ByteOrder, BigEndian, LittleEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian, WriteBytesExt};
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 
-const IDENT: u32 = 0x54494d32;
+pub(crate) const IDENT: u32 = 0x54494d32;
+
+pub(crate) const HEADER_SIZE: usize = 16;
+pub(crate) const ALIGNED_HEADER_SIZE: usize = 128;
+pub(crate) const FRAME_HEADER_SIZE: usize = 48;
 
 #[derive(Debug)]
-struct Header {
-	version: u8,
-	align: u8,
-	count: usize,
+pub(crate) struct FileHeader {
+	pub(crate) version: u8,
+	pub(crate) align: u8,
+	pub(crate) count: usize,
 }
 
-impl Header {
-	fn read(buffer: &[u8], offset: &mut usize) -> Result<Header, Error> {
-		let mut load_part = |size| { get_slice(&buffer, offset, size) };
-		let identifier = BigEndian::read_u32(load_part(4));
-		let version = load_part(1)[0];
-		let align = load_part(1)[0];
-		let count = LittleEndian::read_u16(load_part(2)) as usize;
+impl FileHeader {
+	pub(crate) fn read(buffer: &[u8], offset: &mut usize) -> Result<FileHeader, Error> {
+		let mut load_part = |size| { get_slice(buffer, offset, size) };
+		let identifier = BigEndian::read_u32(load_part(4)?);
+		let version = load_part(1)?[0];
+		let align = load_part(1)?[0];
+		let count = LittleEndian::read_u16(load_part(2)?) as usize;
 
-		load_part(8); // skip over unused data
+		load_part(8)?; // skip over unused data
 
 		if identifier != IDENT {
 			return Err(Error::InvalidIdentifier(identifier))
@@ -34,32 +38,100 @@ impl Header {
 			return Err(Error::InvalidAlignment(align))
 		}
 
-		Ok(Header {
+		Ok(FileHeader {
 			version,
 			align,
 			count,
 		})
 	}
+
+	fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+		writer.write_u32::<BigEndian>(IDENT)?;
+		writer.write_u8(self.version)?;
+		writer.write_u8(self.align)?;
+		writer.write_u16::<LittleEndian>(self.count as u16)?;
+		writer.write_all(&[0u8; 8])?;
+		writer.write_all(&vec![0u8; self.size() - HEADER_SIZE])?;
+
+		Ok(())
+	}
+
+	/// Size of the file header, padded to 128 bytes when the file is aligned.
+	pub(crate) fn size(&self) -> usize {
+		if self.align == 1 { ALIGNED_HEADER_SIZE } else { HEADER_SIZE }
+	}
+
+	/// Rounds a frame offset up to the 128 byte alignment of aligned files.
+	pub(crate) fn align_offset(&self, offset: u64) -> u64 {
+		if self.align == 1 {
+			offset.next_multiple_of(ALIGNED_HEADER_SIZE as u64)
+		} else {
+			offset
+		}
+	}
 }
 
 #[derive(Debug)]
 pub struct Image {
-	header: Header,
+	header: FileHeader,
 	frames: Vec<Frame>,
 }
 
 impl Image {
+	/// Creates an image holding the given frames.
+	pub fn new(frames: Vec<Frame>) -> Image {
+		let header = FileHeader {
+			version: 4,
+			align: 0,
+			count: frames.len(),
+		};
+
+		Image { header, frames }
+	}
+
 	fn read(buffer: &[u8], offset: &mut usize) -> Result<Image, Error> {
-		let header = Header::read(buffer, offset)?;
-		let mut frames = Vec::with_capacity(header.count);
+		let header = FileHeader::read(buffer, offset)?;
+		// the count comes from the file, so it can't be trusted with the allocation
+		let mut frames = Vec::with_capacity(header.count.min(buffer.len() / FRAME_HEADER_SIZE));
+
+		*offset += header.size() - HEADER_SIZE;
 
 		for _ in 0..header.count {
-			frames.push(Frame::read(buffer, offset)?);
+			let mut frame_offset = *offset;
+			let frame = Frame::read(buffer, &mut frame_offset)?;
+
+			// frames are found by their declared size, which has to fit in the buffer
+			get_slice(buffer, offset, frame.header().frame_size() as usize)?;
+			*offset = header.align_offset(*offset as u64) as usize;
+			frames.push(frame);
 		}
 
 		Ok(Image { header, frames })
 	}
 
+	/// Serializes the image and all of its frames.
+	pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+		let header = FileHeader {
+			count: self.frames.len(),
+			..self.header
+		};
+
+		header.write(writer)?;
+
+		let mut offset = header.size() as u64;
+
+		for frame in &self.frames {
+			let end = header.align_offset(offset + frame.header().frame_size());
+
+			frame.write(writer)?;
+			offset += frame.header().parts_size();
+			writer.write_all(&vec![0u8; (end - offset) as usize])?;
+			offset = end;
+		}
+
+		Ok(())
+	}
+
 	pub fn version(&self) -> u8 {
 		self.header.version
 	}
@@ -75,6 +147,10 @@ impl Image {
 	pub fn get_frame(&self, index: usize) -> &Frame {
 		&self.frames[index]
 	}
+
+	pub fn get_frame_mut(&mut self, index: usize) -> &mut Frame {
+		&mut self.frames[index]
+	}
 }
 
 /// Loads a TIM2 image file into memory from buffer.
@@ -85,23 +161,21 @@ impl Image {
 /// use std::fs::File;
 /// use std::io::prelude::*;
 ///
-/// fn main() {
-///     let mut buffer = Vec::new();
-///     let mut file = File::open("../assets/test.tm2").unwrap();
-///     file.read_to_end(&mut buffer).unwrap();
+/// let mut buffer = Vec::new();
+/// let mut file = File::open("../assets/test.tm2").unwrap();
+/// file.read_to_end(&mut buffer).unwrap();
 ///
-///     let image = tim2::from_buffer(&buffer).unwrap();
+/// let image = tim2::from_buffer(&buffer).unwrap();
 ///
-///     /* print the header info for each frame found */
-///     for (i, frame) in image.frames().iter().enumerate() {
-///         println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
-///     }
+/// /* print the header info for each frame found */
+/// for (i, frame) in image.frames().iter().enumerate() {
+///     println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
 /// }
 /// ```
 pub fn from_buffer(buffer: &[u8]) -> Result<Image, Error> {
 	let mut offset = 0usize;
 
-	Image::read(&buffer, &mut offset)
+	Image::read(buffer, &mut offset)
 }
 
 /// Loads a TIM2 image file into memory.
@@ -109,13 +183,11 @@ pub fn from_buffer(buffer: &[u8]) -> Result<Image, Error> {
 /// # Examples
 ///
 /// ```
-/// fn main() {
-///     let image = tim2::load("../assets/test.tm2").unwrap();
+/// let image = tim2::load("../assets/test.tm2").unwrap();
 ///
-///     /* print the header info for each frame found */
-///     for (i, frame) in image.frames().iter().enumerate() {
-///         println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
-///     }
+/// /* print the header info for each frame found */
+/// for (i, frame) in image.frames().iter().enumerate() {
+///     println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
 /// }
 /// ```
 pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, Error> {
@@ -127,3 +199,39 @@ pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, Error> {
 
 	Image::read(&buffer, &mut offset)
 }
+
+/// Serializes a TIM2 image into a new buffer.
+///
+/// # Examples
+///
+/// ```
+/// let buffer = std::fs::read("../assets/test.tm2").unwrap();
+/// let image = tim2::from_buffer(&buffer).unwrap();
+///
+/// assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+/// ```
+pub fn to_buffer(image: &Image) -> Result<Vec<u8>, Error> {
+	let mut buffer = Vec::new();
+
+	image.write(&mut buffer)?;
+
+	Ok(buffer)
+}
+
+/// Writes a TIM2 image file to disk.
+///
+/// # Examples
+///
+/// ```
+/// let image = tim2::load("../assets/test.tm2").unwrap();
+/// let path = std::env::temp_dir().join("test.tm2");
+///
+/// tim2::save(&image, &path).unwrap();
+/// ```
+pub fn save<P: AsRef<Path>>(image: &Image, path: P) -> Result<(), Error> {
+	let mut file = File::create(path)?;
+
+	image.write(&mut file)?;
+
+	Ok(())
+}
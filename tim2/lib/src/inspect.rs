@@ -0,0 +1,72 @@
+use crate::error::Error;
+use crate::frame::Header;
+use crate::stream::read_from;
+
+use std::io::Cursor;
+
+/// Location and header of one frame inside a TIM2 file.
+#[derive(Debug)]
+pub struct FrameLayout {
+	/// Offset of the frame header.
+	pub offset: u64,
+
+	/// Offset of the first image level.
+	pub image_offset: u64,
+
+	/// Offset of the CLUT, if the frame has one.
+	pub clut_offset: Option<u64>,
+
+	/// Offset just past the end of the frame.
+	pub end_offset: u64,
+	pub header: Header,
+}
+
+/// Structured dump of a TIM2 file's headers, offsets and sizes.
+#[derive(Debug)]
+pub struct Inspection {
+	pub version: u8,
+	pub align: u8,
+	pub file_size: u64,
+	pub frames: Vec<FrameLayout>,
+}
+
+/// Reads every header of a TIM2 buffer without decoding pixel data.
+///
+/// # Examples
+///
+/// ```
+/// let buffer = std::fs::read("../assets/test.tm2").unwrap();
+/// let inspection = tim2::inspect(&buffer).unwrap();
+///
+/// for frame in &inspection.frames {
+///     println!("frame @{}: {} bytes", frame.offset, frame.header.total_size());
+/// }
+/// ```
+pub fn inspect(buffer: &[u8]) -> Result<Inspection, Error> {
+	let stream = read_from(Cursor::new(buffer))?;
+	let version = stream.version();
+	let align = stream.align();
+	let mut frames = Vec::with_capacity(stream.frames().len());
+
+	for frame in stream.into_frames() {
+		let offset = frame.offset();
+		let header = frame.into_header();
+		let image_offset = offset + header.header_size() as u64;
+		let clut_offset = image_offset + header.image_size() as u64;
+
+		frames.push(FrameLayout {
+			offset,
+			image_offset,
+			clut_offset: if header.is_paletted() { Some(clut_offset) } else { None },
+			end_offset: offset + header.frame_size(),
+			header,
+		});
+	}
+
+	Ok(Inspection {
+		version,
+		align,
+		file_size: buffer.len() as u64,
+		frames,
+	})
+}
@@ -0,0 +1,120 @@
+use crate::error::{Error, Result};
+use crate::frame::{Frame, PixelBuffer};
+use crate::pixel::{Format, Pixel};
+use crate::quantize::QuantizeOptions;
+
+use ::image::{DynamicImage, Rgba, RgbaImage};
+
+const SWATCH_WIDTH: u32 = 16;
+
+fn to_image(width: u16, height: u16, raw: Vec<u8>) -> RgbaImage {
+	RgbaImage::from_raw(width as u32, height as u32, raw).expect("frame buffer matches its dimensions")
+}
+
+impl Frame {
+	/// Converts the full size image to an `image::RgbaImage`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let image = tim2::load("../assets/test.tm2").unwrap();
+	/// let rgba = image.get_frame(0).to_rgba_image(Some(tim2::COLOR_KEY));
+	///
+	/// assert_eq!(rgba.width(), image.get_frame(0).header().width() as u32);
+	/// ```
+	pub fn to_rgba_image(&self, color_key: Option<Pixel>) -> RgbaImage {
+		to_image(self.header().width(), self.header().height(), self.to_raw(color_key))
+	}
+
+	/// Converts the full size image to an `image::RgbaImage` using the palette at `index`.
+	pub fn to_rgba_image_with_palette(&self, index: usize, color_key: Option<Pixel>) -> Result<RgbaImage> {
+		let raw = self.to_raw_with_palette(index, color_key)?;
+
+		Ok(to_image(self.header().width(), self.header().height(), raw))
+	}
+
+	/// Converts one mipmap level to an `image::RgbaImage`.
+	pub fn mip_level_to_rgba_image(&self, level: usize, color_key: Option<Pixel>) -> Option<RgbaImage> {
+		let (width, height) = self.header().level_size(level);
+		let raw = self.mip_level_to_raw(level, color_key)?;
+
+		Some(to_image(width, height, raw))
+	}
+
+	/// Builds a frame in `format` from any `image::DynamicImage`.
+	///
+	/// Indexed formats keep the exact colors when they fit in the palette, and are
+	/// quantized with `options` otherwise.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use image::{DynamicImage, Rgba, RgbaImage};
+	/// use tim2::{Format, Frame, QuantizeOptions};
+	///
+	/// let source = RgbaImage::from_fn(16, 16, |x, y| Rgba([x as u8 * 16, y as u8 * 16, 0, 255]));
+	/// let frame = Frame::from_image(&DynamicImage::ImageRgba8(source.clone()), Format::Indexed8, QuantizeOptions::default()).unwrap();
+	///
+	/// assert_eq!(frame.to_rgba_image(None), source);
+	/// ```
+	pub fn from_image(image: &DynamicImage, format: Format, options: QuantizeOptions) -> Result<Frame> {
+		let rgba = image.to_rgba8();
+		let (width, height) = rgba.dimensions();
+
+		if width > u16::MAX as u32 || height > u16::MAX as u32 {
+			return Err(Error::InvalidDimensions(width, height));
+		}
+
+		if format.is_indexed() {
+			Frame::from_rgba_quantized(width as u16, height as u16, format, rgba.as_raw(), options)
+		} else {
+			Frame::from_rgba(width as u16, height as u16, format, rgba.as_raw())
+		}
+	}
+
+	/// Builds a frame from any `image::DynamicImage` laid out like `template`, as
+	/// [`Frame::from_template`] does.
+	pub fn from_image_with_template(template: &Frame, image: &DynamicImage, color_key: Option<Pixel>) -> Result<Frame> {
+		let rgba = image.to_rgba8();
+		let (width, height) = rgba.dimensions();
+
+		if width > u16::MAX as u32 || height > u16::MAX as u32 {
+			return Err(Error::InvalidDimensions(width, height));
+		}
+
+		Frame::from_template(template, width as u16, height as u16, rgba.as_raw(), color_key)
+	}
+}
+
+/// Lays a palette out as a strip 16 colors wide, one row per 16 colors.
+///
+/// # Examples
+///
+/// ```
+/// let image = tim2::load("../assets/test.tm2").unwrap();
+/// let palette = &image.get_frame(0).palettes()[0];
+/// let swatch = tim2::palette_to_swatch(palette);
+///
+/// assert_eq!(tim2::swatch_to_palette(&swatch), *palette);
+/// ```
+pub fn palette_to_swatch(palette: &[Pixel]) -> RgbaImage {
+	let height = (palette.len() as u32).div_ceil(SWATCH_WIDTH).max(1);
+
+	RgbaImage::from_fn(SWATCH_WIDTH, height, |x, y| {
+		let color = palette.get((y * SWATCH_WIDTH + x) as usize).copied();
+		let color = color.unwrap_or(Pixel::rgba(0, 0, 0, 0));
+
+		Rgba([color.r(), color.g(), color.b(), color.a()])
+	})
+}
+
+/// Reads the colors of a swatch strip row by row, keeping their alpha.
+pub fn swatch_to_palette(image: &RgbaImage) -> PixelBuffer {
+	image.pixels().map(|v| Pixel::rgba(v[0], v[1], v[2], v[3])).collect()
+}
+
+impl From<&Frame> for RgbaImage {
+	fn from(frame: &Frame) -> RgbaImage {
+		frame.to_rgba_image(None)
+	}
+}
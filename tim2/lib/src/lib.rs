@@ -1,26 +1,47 @@
 //! # tim2
 //! 
-//! An image loader for TIM2 (.tm2) image files
-
-///
-/// ```
-/// fn main() {
-///     let image = tim2::load("../assets/test.tm2").unwrap();
-/// 
-///     /* print the header info for each frame found */
-///     for (i, frame) in image.frames().iter().enumerate() {
-///         println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
-///     }
-/// }
-/// ```
+//! An image loader and writer for TIM2 (.tm2) image files
+//!
+//! ```
+//! let image = tim2::load("../assets/test.tm2").unwrap();
+//!
+//! /* print the header info for each frame found */
+//! for (i, frame) in image.frames().iter().enumerate() {
+//!     println!("frame[{}]: <{}  {}>", i, frame.header().width(), frame.header().height());
+//! }
+//!
+//! tim2::save(&image, std::env::temp_dir().join("test.tm2")).unwrap();
+//! ```
 
 mod common;
+mod compare;
 mod error;
 mod frame;
+mod gs;
 mod image;
+mod inspect;
+#[cfg(feature = "image")]
+mod interop;
+mod palette;
 mod pixel;
+mod quantize;
+mod storage;
+mod stream;
+mod user_data;
+mod validate;
 
+pub use compare::*;
 pub use error::*;
 pub use frame::*;
+pub use gs::*;
 pub use image::*;
+pub use inspect::*;
+#[cfg(feature = "image")]
+pub use interop::*;
+pub use palette::*;
 pub use pixel::*;
+pub use quantize::*;
+pub use storage::*;
+pub use stream::*;
+pub use user_data::*;
+pub use validate::*;
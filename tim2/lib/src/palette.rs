@@ -0,0 +1,162 @@
+use crate::error::{Error, Result};
+use crate::frame::PixelBuffer;
+use crate::pixel::Pixel;
+
+use std::fmt::Write;
+
+const ACT_SIZE: usize = 768;
+
+/// File formats palettes can be exported to and imported from.
+///
+/// None of them store alpha, so imported colors are opaque. The PNG swatch strip, which
+/// keeps alpha, comes with the `image` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaletteFormat {
+	/// Paint Shop Pro `.pal`.
+	Jasc,
+
+	/// GIMP `.gpl`.
+	Gimp,
+
+	/// Adobe `.act`.
+	Act,
+}
+
+impl PaletteFormat {
+	/// Extension used by files of this format.
+	pub fn extension(&self) -> &'static str {
+		match self {
+			PaletteFormat::Jasc => "pal",
+			PaletteFormat::Gimp => "gpl",
+			PaletteFormat::Act => "act",
+		}
+	}
+}
+
+/// Serializes a palette, naming it `name` where the format has room for one.
+///
+/// ```
+/// use tim2::{Pixel, PaletteFormat};
+///
+/// let palette = vec![Pixel::rgba(0, 255, 0, 255), Pixel::rgba(16, 32, 48, 255)];
+/// let bytes = tim2::export_palette(&palette, "cecil", PaletteFormat::Jasc);
+///
+/// assert_eq!(bytes, b"JASC-PAL\r\n0100\r\n2\r\n0 255 0\r\n16 32 48\r\n");
+/// ```
+pub fn export_palette(palette: &[Pixel], name: &str, format: PaletteFormat) -> Vec<u8> {
+	match format {
+		PaletteFormat::Jasc => {
+			let mut result = format!("JASC-PAL\r\n0100\r\n{}\r\n", palette.len());
+
+			for pixel in palette {
+				let _ = write!(result, "{} {} {}\r\n", pixel.r(), pixel.g(), pixel.b());
+			}
+
+			result.into_bytes()
+		},
+		PaletteFormat::Gimp => {
+			let mut result = format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", name);
+
+			for (i, pixel) in palette.iter().enumerate() {
+				let _ = writeln!(result, "{:3} {:3} {:3}\tIndex {}", pixel.r(), pixel.g(), pixel.b(), i);
+			}
+
+			result.into_bytes()
+		},
+		PaletteFormat::Act => {
+			let mut result = Vec::with_capacity(ACT_SIZE + 4);
+
+			for pixel in palette.iter().take(256) {
+				result.extend_from_slice(&[pixel.r(), pixel.g(), pixel.b()]);
+			}
+
+			result.resize(ACT_SIZE, 0);
+			result.extend_from_slice(&(palette.len().min(256) as u16).to_be_bytes());
+			result.extend_from_slice(&[0xFF, 0xFF]); // no transparent index
+			result
+		},
+	}
+}
+
+/// Parses a palette file into opaque colors.
+pub fn import_palette(bytes: &[u8], format: PaletteFormat) -> Result<PixelBuffer> {
+	match format {
+		PaletteFormat::Jasc => import_jasc(bytes),
+		PaletteFormat::Gimp => import_gimp(bytes),
+		PaletteFormat::Act => import_act(bytes),
+	}
+}
+
+fn parse_color<'a, I: Iterator<Item = &'a str>>(mut fields: I, line: usize) -> Result<Pixel> {
+	let mut channel = || {
+		fields
+			.next()
+			.and_then(|v| v.parse::<u8>().ok())
+			.ok_or(Error::MalformedPalette(line))
+	};
+
+	Ok(Pixel::rgba(channel()?, channel()?, channel()?, 255))
+}
+
+fn import_jasc(bytes: &[u8]) -> Result<PixelBuffer> {
+	let text = String::from_utf8_lossy(bytes);
+	let mut lines = text.lines().map(str::trim).enumerate();
+	let mut next = |expected: Option<&str>| match lines.next() {
+		Some((i, v)) if expected.is_none_or(|e| e == v) => Ok((i + 1, v)),
+		Some((i, _)) => Err(Error::MalformedPalette(i + 1)),
+		None => Err(Error::MalformedPalette(0)),
+	};
+
+	next(Some("JASC-PAL"))?;
+	next(Some("0100"))?;
+
+	let (line, count) = next(None)?;
+	let count = count.parse::<usize>().map_err(|_| Error::MalformedPalette(line))?;
+	let mut result = Vec::with_capacity(count.min(256));
+
+	for _ in 0..count {
+		let (line, color) = next(None)?;
+
+		result.push(parse_color(color.split_whitespace(), line)?);
+	}
+
+	Ok(result)
+}
+
+fn import_gimp(bytes: &[u8]) -> Result<PixelBuffer> {
+	let text = String::from_utf8_lossy(bytes);
+	let mut lines = text.lines().enumerate();
+	let mut result = Vec::new();
+
+	match lines.next() {
+		Some((_, v)) if v.trim() == "GIMP Palette" => {},
+		_ => return Err(Error::MalformedPalette(1)),
+	}
+
+	for (i, line) in lines {
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+			continue;
+		}
+
+		result.push(parse_color(line.split_whitespace(), i + 1)?);
+	}
+
+	Ok(result)
+}
+
+fn import_act(bytes: &[u8]) -> Result<PixelBuffer> {
+	let count = match bytes.len() {
+		ACT_SIZE => 256,
+		n if n == ACT_SIZE + 4 => (u16::from_be_bytes([bytes[ACT_SIZE], bytes[ACT_SIZE + 1]]) as usize).min(256),
+		n => return Err(Error::InvalidBufferSize(ACT_SIZE, n)),
+	};
+
+	let colors = bytes[..count * 3]
+		.chunks_exact(3)
+		.map(|v| Pixel::rgba(v[0], v[1], v[2], 255))
+		.collect();
+
+	Ok(colors)
+}
@@ -2,7 +2,7 @@ use crate::error::Error;
 
 use std::fmt;
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Format {
 	Indexed4,
 	Indexed8,
@@ -11,7 +11,56 @@ pub enum Format {
 	Rgba8888,
 }
 
+impl Format {
+	pub fn bpp(&self) -> u8 {
+		match self {
+			Format::Indexed4 => 4,
+			Format::Indexed8 => 8,
+			Format::Abgr1555 => 16,
+			Format::Rgb888 => 24,
+			Format::Rgba8888 => 32,
+		}
+	}
+
+	pub fn is_indexed(&self) -> bool {
+		matches!(self, Format::Indexed4 | Format::Indexed8)
+	}
+}
+
+/// How the alpha channel of decoded colors is mapped to RGBA8.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlphaMode {
+	/// Passes the stored alpha through unchanged.
+	Raw,
+
+	/// Maps the PS2 range, where 0x80 is fully opaque, onto 0..=255.
+	Ps2Scaled,
+}
+
+impl AlphaMode {
+	pub fn apply(self, alpha: u8) -> u8 {
+		match self {
+			AlphaMode::Raw => alpha,
+			AlphaMode::Ps2Scaled => ((alpha.min(0x80) as u16 * 255 + 0x40) / 0x80) as u8,
+		}
+	}
+}
+
+/// Options for converting decoded pixels to RGBA8.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+	pub alpha: AlphaMode,
+}
+
+impl Default for DecodeOptions {
+	fn default() -> Self {
+		DecodeOptions {
+			alpha: AlphaMode::Raw,
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Pixel {
 	r: u8,
 	g: u8,
@@ -19,28 +68,23 @@ pub struct Pixel {
 	a: u8,
 }
 
+/// The green color key the game uses to mark transparent pixels.
+pub const COLOR_KEY: Pixel = Pixel { r: 0, g: 255, b: 0, a: 255 };
+
 impl Pixel {
 	pub fn new() -> Pixel {
 		Pixel { r: 255, g: 255, b: 255, a: 255 }
 	}
 
-	pub fn from(r: u8, g: u8, b: u8, a: u8) -> Pixel {
+	/// Builds a pixel from its four channels.
+	pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Pixel {
 		Pixel { r, g, b, a }
 	}
 
 	pub fn from_buf(buf: &[u8]) -> Result<Pixel, Error> {
 		match buf.len() {
-			// 16-bit: RGBA5551
-			2 => {
-				let raw = ((buf[0] as u16) << 8) | buf[1] as u16;
-
-				Ok(Pixel {
-					r: ((raw & 0x001F) as f32 / 31.0 * 255.0) as u8,
-					g: (((raw >> 5) & 0x001F) as f32 / 31.0 * 255.0) as u8,
-					b: (((raw >> 10) & 0x001F) as f32 / 31.0 * 255.0) as u8,
-					a: if raw >> 15 == 1 { 255 } else { 0 },
-				})
-			},
+			// 16-bit: little-endian ABGR1555
+			2 => Ok(Color1555(u16::from_le_bytes([buf[0], buf[1]])).into()),
 
 			// 24-bit: RGB8
 			3 => Ok(Pixel {
@@ -61,6 +105,38 @@ impl Pixel {
 		}
 	}
 
+	pub fn to_buf(&self, size: usize) -> Result<Vec<u8>, Error> {
+		match size {
+			// 16-bit: little-endian ABGR1555
+			2 => Ok(Color1555::from(*self).0.to_le_bytes().to_vec()),
+
+			// 24-bit: RGB8
+			3 => Ok(vec![self.r, self.g, self.b]),
+
+			// 32-bit: RGBA8
+			4 => Ok(vec![self.r, self.g, self.b, self.a]),
+			n => Err(Error::InvalidPixelSize(n)),
+		}
+	}
+
+	/// Encodes the color the way `format` stores it. Indexed formats have no color encoding.
+	///
+	/// ```
+	/// use tim2::{Format, Pixel};
+	///
+	/// let pixel = Pixel::rgba(255, 0, 0, 255);
+	///
+	/// assert_eq!(pixel.to_bytes(Format::Abgr1555).unwrap(), vec![0x1F, 0x80]);
+	/// assert_eq!(pixel.to_bytes(Format::Rgb888).unwrap(), vec![255, 0, 0]);
+	/// ```
+	pub fn to_bytes(&self, format: Format) -> Result<Vec<u8>, Error> {
+		if format.is_indexed() {
+			return Err(Error::UnsupportedFormat(format));
+		}
+
+		self.to_buf(format.bpp() as usize / 8)
+	}
+
 	pub fn r(&self) -> u8 { self.r }
 
 	pub fn g(&self) -> u8 { self.g }
@@ -79,3 +155,63 @@ impl fmt::Display for Pixel {
 		write!(f, "px<{}  {}  {}  {}>", self.r(), self.g(), self.b(), self.a())
 	}
 }
+
+impl From<[u8; 4]> for Pixel {
+	fn from(v: [u8; 4]) -> Self {
+		Pixel::rgba(v[0], v[1], v[2], v[3])
+	}
+}
+
+impl From<Pixel> for [u8; 4] {
+	fn from(v: Pixel) -> Self {
+		[v.r, v.g, v.b, v.a]
+	}
+}
+
+/// Packs a color as `0xRRGGBBAA`.
+impl From<u32> for Pixel {
+	fn from(v: u32) -> Self {
+		v.to_be_bytes().into()
+	}
+}
+
+impl From<Pixel> for u32 {
+	fn from(v: Pixel) -> Self {
+		u32::from_be_bytes(v.into())
+	}
+}
+
+/// A 16-bit color with 5 bits per channel, red in the low bits and the alpha flag on top.
+///
+/// ```
+/// use tim2::{Color1555, Pixel};
+///
+/// let pixel: Pixel = Color1555(0x801F).into();
+///
+/// assert_eq!(pixel, Pixel::rgba(255, 0, 0, 255));
+/// assert_eq!(Color1555::from(pixel), Color1555(0x801F));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Color1555(pub u16);
+
+impl From<Color1555> for Pixel {
+	fn from(v: Color1555) -> Self {
+		let channel = |shift: u16| (((v.0 >> shift) & 0x1F) as f32 / 31.0 * 255.0) as u8;
+
+		Pixel {
+			r: channel(0),
+			g: channel(5),
+			b: channel(10),
+			a: if v.0 >> 15 == 1 { 255 } else { 0 },
+		}
+	}
+}
+
+impl From<Pixel> for Color1555 {
+	fn from(v: Pixel) -> Self {
+		let channel = |v: u8| (v as f32 / 255.0 * 31.0).round() as u16;
+		let alpha = if v.a >= 0x80 { 1 } else { 0 };
+
+		Color1555(channel(v.r) | (channel(v.g) << 5) | (channel(v.b) << 10) | (alpha << 15))
+	}
+}
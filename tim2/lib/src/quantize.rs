@@ -0,0 +1,245 @@
+use crate::error::{Error, Result};
+use crate::frame::PixelBuffer;
+use crate::pixel::{Pixel, COLOR_KEY};
+
+use std::collections::HashMap;
+
+/// Options for reducing an RGBA image to a palette.
+#[derive(Debug, Copy, Clone)]
+pub struct QuantizeOptions {
+	/// Diffuses the quantization error with Floyd-Steinberg dithering.
+	pub dither: bool,
+
+	/// Color that always keeps its own palette entry, at index 0, when it appears in the image.
+	pub color_key: Option<Pixel>,
+}
+
+impl Default for QuantizeOptions {
+	fn default() -> Self {
+		QuantizeOptions {
+			dither: false,
+			color_key: Some(COLOR_KEY),
+		}
+	}
+}
+
+/// An image reduced to palette indices.
+#[derive(Debug)]
+pub struct Quantized {
+	pub indices: Vec<u8>,
+	pub palette: PixelBuffer,
+}
+
+struct ColorBox {
+	colors: Vec<(Pixel, u32)>,
+}
+
+impl ColorBox {
+	fn channels(pixel: &Pixel) -> [u8; 4] {
+		[pixel.r(), pixel.g(), pixel.b(), pixel.a()]
+	}
+
+	fn widest_channel(&self) -> (usize, u8) {
+		let mut result = (0, 0);
+
+		for channel in 0..4 {
+			let values = self.colors.iter().map(|(v, _)| Self::channels(v)[channel]);
+			let min = values.clone().min().unwrap_or(0);
+			let max = values.max().unwrap_or(0);
+
+			if max - min > result.1 {
+				result = (channel, max - min);
+			}
+		}
+
+		result
+	}
+
+	fn split(mut self) -> (ColorBox, ColorBox) {
+		let (channel, _) = self.widest_channel();
+
+		// ties are broken on every channel, as colors come out of the histogram in any order
+		self.colors.sort_by_key(|(v, _)| (Self::channels(v)[channel], Self::channels(v)));
+
+		let total: u64 = self.colors.iter().map(|(_, n)| *n as u64).sum();
+		let mut seen = 0u64;
+		let mut median = 1;
+
+		for (i, (_, count)) in self.colors.iter().enumerate() {
+			seen += *count as u64;
+
+			if seen * 2 >= total {
+				median = (i + 1).clamp(1, self.colors.len() - 1);
+				break;
+			}
+		}
+
+		let upper = self.colors.split_off(median);
+
+		(self, ColorBox { colors: upper })
+	}
+
+	fn average(&self) -> Pixel {
+		let mut sums = [0u64; 4];
+		let mut total = 0u64;
+
+		for (pixel, count) in &self.colors {
+			for (sum, value) in sums.iter_mut().zip(Self::channels(pixel).iter()) {
+				*sum += *value as u64 * *count as u64;
+			}
+
+			total += *count as u64;
+		}
+
+		let channel = |i: usize| ((sums[i] + total / 2) / total.max(1)) as u8;
+
+		Pixel::rgba(channel(0), channel(1), channel(2), channel(3))
+	}
+}
+
+fn median_cut(histogram: HashMap<Pixel, u32>, color_count: usize) -> PixelBuffer {
+	let mut colors: Vec<(Pixel, u32)> = histogram.into_iter().collect();
+
+	if colors.len() <= color_count {
+		colors.sort_by_key(|(v, _)| ColorBox::channels(v));
+
+		return colors.into_iter().map(|(v, _)| v).collect();
+	}
+
+	let mut boxes = vec![ColorBox { colors }];
+
+	while boxes.len() < color_count {
+		let candidate = boxes
+			.iter()
+			.enumerate()
+			.filter(|(_, v)| v.colors.len() > 1)
+			.max_by_key(|(_, v)| v.widest_channel().1)
+			.map(|(i, _)| i);
+
+		let index = match candidate {
+			Some(i) => i,
+			None => break,
+		};
+
+		let (lower, upper) = boxes.swap_remove(index).split();
+
+		boxes.push(lower);
+		boxes.push(upper);
+	}
+
+	boxes.iter().map(|v| v.average()).collect()
+}
+
+fn distance(a: [f32; 4], b: &Pixel) -> f32 {
+	let b = ColorBox::channels(b);
+
+	(0..4).map(|i| (a[i] - b[i] as f32).powi(2)).sum()
+}
+
+fn nearest(palette: &[Pixel], color: [f32; 4], skip: usize) -> usize {
+	let mut result = skip;
+	let mut best = f32::MAX;
+
+	for (i, entry) in palette.iter().enumerate().skip(skip) {
+		let d = distance(color, entry);
+
+		if d < best {
+			best = d;
+			result = i;
+		}
+	}
+
+	result
+}
+
+/// Reduces an RGBA8 image to at most `color_count` colors using median cut.
+///
+/// Pixels that match `options.color_key` are never merged with other colors, so
+/// transparency keyed on that color survives the conversion.
+pub fn quantize(
+	width: u16,
+	height: u16,
+	rgba: &[u8],
+	color_count: usize,
+	options: QuantizeOptions,
+) -> Result<Quantized> {
+	let width = width as usize;
+	let expected = width * height as usize * 4;
+
+	if !(2..=256).contains(&color_count) {
+		return Err(Error::InvalidColorCount(color_count));
+	}
+
+	if rgba.len() != expected {
+		return Err(Error::InvalidBufferSize(expected, rgba.len()));
+	}
+
+	let pixels: PixelBuffer = rgba
+		.chunks(4)
+		.map(|v| Pixel::rgba(v[0], v[1], v[2], v[3]))
+		.collect();
+
+	let is_key = |pixel: &Pixel| options.color_key == Some(*pixel);
+	let has_key = pixels.iter().any(is_key);
+	let reserved = if has_key { 1 } else { 0 };
+	let mut histogram = HashMap::new();
+
+	for pixel in pixels.iter().filter(|v| !is_key(v)) {
+		*histogram.entry(*pixel).or_insert(0u32) += 1;
+	}
+
+	let mut palette = Vec::with_capacity(color_count);
+
+	if let (true, Some(key)) = (has_key, options.color_key) {
+		palette.push(key);
+	}
+
+	palette.append(&mut median_cut(histogram, color_count - reserved));
+
+	let mut indices = Vec::with_capacity(pixels.len());
+	let mut errors = vec![[0f32; 4]; pixels.len()];
+
+	for (i, pixel) in pixels.iter().enumerate() {
+		if is_key(pixel) {
+			indices.push(0);
+			continue;
+		}
+
+		let mut color = [0f32; 4];
+
+		for (c, value) in ColorBox::channels(pixel).iter().enumerate() {
+			color[c] = (*value as f32 + errors[i][c]).clamp(0.0, 255.0);
+		}
+
+		let index = nearest(&palette, color, reserved);
+
+		indices.push(index as u8);
+
+		if !options.dither {
+			continue;
+		}
+
+		let chosen = ColorBox::channels(&palette[index]);
+		let x = i % width;
+		let mut spread = |target: usize, weight: f32| {
+			if target < pixels.len() && !is_key(&pixels[target]) {
+				for c in 0..4 {
+					errors[target][c] += (color[c] - chosen[c] as f32) * weight;
+				}
+			}
+		};
+
+		if x + 1 < width {
+			spread(i + 1, 7.0 / 16.0);
+			spread(i + width + 1, 1.0 / 16.0);
+		}
+
+		if x > 0 {
+			spread(i + width - 1, 3.0 / 16.0);
+		}
+
+		spread(i + width, 5.0 / 16.0);
+	}
+
+	Ok(Quantized { indices, palette })
+}
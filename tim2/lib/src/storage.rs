@@ -0,0 +1,136 @@
+use crate::gs::{Psm, Tex0};
+
+const SWIZZLE_WIDTH_BYTES: usize = 16;
+const SWIZZLE_HEIGHT: usize = 8;
+
+/// How the texels of an image level are ordered in the file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Storage {
+	/// Rows stored top to bottom, the way the GS expects them on upload.
+	Linear,
+
+	/// Blocks of 16 bytes by 8 rows, the PSP texture swizzle. Blocks are 32 texels wide at
+	/// 4 bits per pixel, 16 at 8 bits and so on.
+	Swizzled,
+
+	/// 8-bit texels in the order they sit in GS memory after an upload as PSMCT32 data.
+	Interlaced,
+}
+
+impl Storage {
+	/// Works out the storage mode of an image from the TEX0 register of its frame.
+	///
+	/// PS2 files set PSM to the texel format and TBW to the buffer width, and keep texels
+	/// linear. 8-bit texels declared as PSMCT32 were dumped straight from GS memory. Anything
+	/// else, such as the zero or float values PSP files keep in TEX0, is swizzled. The PSP has
+	/// no 24-bit textures, so those are always linear.
+	pub fn detect(tex_0: &Tex0, bpp: u8, width: u16, height: u16) -> Storage {
+		let fits = tex_0.tbw > 0
+			&& tex_0.tbw as u32 * 64 >= width as u32
+			&& tex_0.width() >= width as u32
+			&& tex_0.height() >= height as u32;
+
+		let psm_matches = matches!(
+			(tex_0.psm, bpp),
+			(Psm::Psmt4, 4)
+				| (Psm::Psmt4hl, 4)
+				| (Psm::Psmt4hh, 4)
+				| (Psm::Psmt8, 8)
+				| (Psm::Psmt8h, 8)
+				| (Psm::Psmct16, 16)
+				| (Psm::Psmct16s, 16)
+				| (Psm::Psmct24, 24)
+				| (Psm::Psmct32, 32)
+		);
+
+		let aligned = width.is_multiple_of(16) && height.is_multiple_of(16);
+
+		if bpp == 24 || (fits && psm_matches) {
+			Storage::Linear
+		} else if fits && aligned && tex_0.psm == Psm::Psmct32 && bpp == 8 {
+			Storage::Interlaced
+		} else {
+			Storage::Swizzled
+		}
+	}
+
+	/// Number of texels stored for an image, including the padding of partial blocks.
+	pub(crate) fn stored_len(self, width: u16, height: u16, bpp: u8) -> usize {
+		let width = width as usize;
+		let height = height as usize;
+
+		match self {
+			Storage::Swizzled => {
+				let block_width = Storage::block_width(bpp);
+				let block_count_x = width.div_ceil(block_width);
+				let block_count_y = height.div_ceil(SWIZZLE_HEIGHT);
+
+				block_count_x * block_width * block_count_y * SWIZZLE_HEIGHT
+			},
+			_ => width * height,
+		}
+	}
+
+	/// Reorders stored texels into rows, filling texels missing from `buffer` with `fill`.
+	pub(crate) fn decode<T: Copy>(self, buffer: &[T], width: u16, height: u16, bpp: u8, fill: T) -> Vec<T> {
+		let width = width as usize;
+		let height = height as usize;
+		let mut result = Vec::with_capacity(width * height);
+
+		for y in 0..height {
+			for x in 0..width {
+				let index = self.offset(x, y, width, bpp);
+
+				result.push(buffer.get(index).copied().unwrap_or(fill));
+			}
+		}
+
+		result
+	}
+
+	/// Reorders rows of texels into stored order, padding partial blocks with `fill`.
+	pub(crate) fn encode<T: Copy>(self, buffer: &[T], width: u16, height: u16, bpp: u8, fill: T) -> Vec<T> {
+		let mut result = vec![fill; self.stored_len(width, height, bpp)];
+		let width = width as usize;
+		let height = height as usize;
+
+		for y in 0..height {
+			for x in 0..width {
+				let index = self.offset(x, y, width, bpp);
+
+				if let (Some(target), Some(value)) = (result.get_mut(index), buffer.get(y * width + x)) {
+					*target = *value;
+				}
+			}
+		}
+
+		result
+	}
+
+	fn block_width(bpp: u8) -> usize {
+		(SWIZZLE_WIDTH_BYTES * 8 / bpp.max(1) as usize).max(1)
+	}
+
+	fn offset(self, x: usize, y: usize, width: usize, bpp: u8) -> usize {
+		match self {
+			Storage::Linear => y * width + x,
+			Storage::Swizzled => {
+				let block_width = Storage::block_width(bpp);
+				let block_count_x = width.div_ceil(block_width);
+				let block = (y / SWIZZLE_HEIGHT) * block_count_x + x / block_width;
+
+				block * block_width * SWIZZLE_HEIGHT + (y % SWIZZLE_HEIGHT) * block_width + x % block_width
+			},
+			Storage::Interlaced => {
+				// each 16x16 block holds four 32-bit columns, with every other row pair swapped
+				let block = (y & !0xF) * width + (x & !0xF) * 2;
+				let swap = ((y + 2) >> 2 & 1) * 4;
+				let row = (((y & !3) >> 1) + (y & 1)) & 7;
+				let column = row * width * 2 + ((x + swap) & 7) * 4;
+				let byte = ((y >> 1) & 1) + ((x >> 2) & 2);
+
+				block + column + byte
+			},
+		}
+	}
+}
@@ -0,0 +1,149 @@
+use crate::error::Error;
+use crate::frame::{Frame, Header};
+use crate::image;
+
+use byteorder::{ByteOrder, LittleEndian};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Fills `buffer` with the bytes at `offset`, reporting a short read as
+/// [`Error::UnexpectedEof`] like reading from a buffer does.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, buffer: &mut [u8]) -> Result<(), Error> {
+	let needed = buffer.len();
+
+	reader.seek(SeekFrom::Start(offset))?;
+	reader.read_exact(buffer).map_err(|err| match err.kind() {
+		io::ErrorKind::UnexpectedEof => Error::UnexpectedEof { offset: offset as usize, needed },
+		_ => Error::Io(err),
+	})
+}
+
+/// A frame whose header has been read but whose pixel data has not been decoded yet.
+#[derive(Debug)]
+pub struct FrameRef {
+	offset: u64,
+	header: Header,
+}
+
+impl FrameRef {
+	fn read<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<FrameRef, Error> {
+		let mut buffer = vec![0u8; image::FRAME_HEADER_SIZE];
+
+		read_at(reader, offset, &mut buffer)?;
+
+		let header_size = LittleEndian::read_u16(&buffer[12..14]) as usize;
+
+		if header_size > image::FRAME_HEADER_SIZE {
+			buffer.resize(header_size, 0);
+			read_at(reader, offset + image::FRAME_HEADER_SIZE as u64, &mut buffer[image::FRAME_HEADER_SIZE..])?;
+		}
+
+		let header = Header::read(&buffer, &mut 0)?;
+
+		Ok(FrameRef { offset, header })
+	}
+
+	/// Byte offset of the frame from the start of the file.
+	pub fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
+
+	pub fn into_header(self) -> Header {
+		self.header
+	}
+
+	/// Reads and decodes the frame's pixel data and palettes.
+	pub fn decode<R: Read + Seek>(&self, reader: &mut R) -> Result<Frame, Error> {
+		let mut buffer = Vec::new();
+
+		reader.seek(SeekFrom::Start(self.offset))?;
+		reader.take(self.header.frame_size()).read_to_end(&mut buffer)?;
+
+		// offsets of a frame cut short count from the start of the file, as they do in `from_buffer`
+		Frame::read(&buffer, &mut 0).map_err(|err| match err {
+			Error::UnexpectedEof { offset, needed } => Error::UnexpectedEof { offset: offset + self.offset as usize, needed },
+			err => err,
+		})
+	}
+}
+
+/// A TIM2 file opened for reading frames on demand.
+#[derive(Debug)]
+pub struct Stream<R> {
+	reader: R,
+	version: u8,
+	align: u8,
+	frames: Vec<FrameRef>,
+}
+
+impl<R: Read + Seek> Stream<R> {
+	pub fn version(&self) -> u8 {
+		self.version
+	}
+
+	pub fn align(&self) -> u8 {
+		self.align
+	}
+
+	pub fn frames(&self) -> &Vec<FrameRef> {
+		&self.frames
+	}
+
+	/// Decodes the frame at `index`, or returns `None` if there is no such frame.
+	pub fn decode(&mut self, index: usize) -> Option<Result<Frame, Error>> {
+		let frame = self.frames.get(index)?;
+
+		Some(frame.decode(&mut self.reader))
+	}
+
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	pub fn into_frames(self) -> Vec<FrameRef> {
+		self.frames
+	}
+}
+
+/// Reads the headers of a TIM2 image without decoding any pixel data.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+///
+/// let file = File::open("../assets/test.tm2").unwrap();
+/// let mut stream = tim2::read_from(file).unwrap();
+///
+/// for frame in stream.frames() {
+///     println!("frame @{}: <{}  {}>", frame.offset(), frame.header().width(), frame.header().height());
+/// }
+///
+/// let frame = stream.decode(0).unwrap().unwrap();
+/// ```
+pub fn read_from<R: Read + Seek>(mut reader: R) -> Result<Stream<R>, Error> {
+	let mut buffer = [0u8; image::HEADER_SIZE];
+
+	read_at(&mut reader, 0, &mut buffer)?;
+
+	let header = image::FileHeader::read(&buffer, &mut 0)?;
+	let mut offset = header.size() as u64;
+	let mut frames = Vec::new();
+
+	for _ in 0..header.count {
+		let frame = FrameRef::read(&mut reader, offset)?;
+
+		offset = header.align_offset(offset + frame.header.frame_size());
+		frames.push(frame);
+	}
+
+	Ok(Stream {
+		reader,
+		version: header.version,
+		align: header.align,
+		frames,
+	})
+}
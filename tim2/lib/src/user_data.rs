@@ -0,0 +1,115 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+const EXTENDED_ID: &[u8; 4] = b"eXt\0";
+const EXTENDED_HEADER_SIZE: usize = 16;
+
+/// User data stored between a frame header and its image data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserData {
+	/// The standard `eXt` extended header: tool specific data followed by a comment.
+	Extended {
+		comment: String,
+		data: Vec<u8>,
+		reserved: u32,
+	},
+
+	/// Anything else, kept byte for byte.
+	Raw(Vec<u8>),
+}
+
+impl UserData {
+	/// Parses the user data area of a frame.
+	///
+	/// Falls back to [`UserData::Raw`] whenever the area isn't an `eXt` header that would be
+	/// written back unchanged, so encoding always reproduces the original bytes.
+	///
+	/// ```
+	/// use tim2::UserData;
+	///
+	/// let mut area = b"eXt\0\x20\0\0\0\0\0\0\0\0\0\0\0".to_vec();
+	///
+	/// area.extend_from_slice(b"rydia\0\0\0\0\0\0\0\0\0\0\0");
+	///
+	/// match UserData::parse(&area) {
+	///     UserData::Extended { comment, .. } => assert_eq!(comment, "rydia"),
+	///     UserData::Raw(_) => panic!("expected an extended header"),
+	/// }
+	/// ```
+	pub fn parse(bytes: &[u8]) -> UserData {
+		match UserData::parse_extended(bytes) {
+			Some(result) if result.encode(bytes.len()) == bytes => result,
+			_ => UserData::Raw(bytes.to_vec()),
+		}
+	}
+
+	fn parse_extended(bytes: &[u8]) -> Option<UserData> {
+		if bytes.len() < EXTENDED_HEADER_SIZE || &bytes[0..4] != EXTENDED_ID {
+			return None;
+		}
+
+		let data_size = LittleEndian::read_u32(&bytes[8..12]) as usize;
+		let reserved = LittleEndian::read_u32(&bytes[12..16]);
+		let data = bytes.get(EXTENDED_HEADER_SIZE..EXTENDED_HEADER_SIZE.checked_add(data_size)?)?;
+		let text = &bytes[EXTENDED_HEADER_SIZE + data_size..];
+		let end = text.iter().position(|v| *v == 0)?;
+		let comment = String::from_utf8(text[..end].to_vec()).ok()?;
+
+		Some(UserData::Extended {
+			comment,
+			data: data.to_vec(),
+			reserved,
+		})
+	}
+
+	/// Serializes the user data, zero padded to `size` bytes.
+	pub(crate) fn encode(&self, size: usize) -> Vec<u8> {
+		let mut result = match self {
+			UserData::Extended { comment, data, reserved } => {
+				let mut result = Vec::with_capacity(size);
+				let mut field = [0u8; 4];
+
+				result.extend_from_slice(EXTENDED_ID);
+				LittleEndian::write_u32(&mut field, size as u32);
+				result.extend_from_slice(&field);
+				LittleEndian::write_u32(&mut field, data.len() as u32);
+				result.extend_from_slice(&field);
+				LittleEndian::write_u32(&mut field, *reserved);
+				result.extend_from_slice(&field);
+				result.extend_from_slice(data);
+				result.extend_from_slice(comment.as_bytes());
+				result.push(0);
+				result
+			},
+			UserData::Raw(bytes) => bytes.clone(),
+		};
+
+		result.resize(size.max(result.len()), 0);
+		result
+	}
+
+	/// Size of the serialized user data before padding.
+	pub fn len(&self) -> usize {
+		match self {
+			UserData::Extended { comment, data, .. } => EXTENDED_HEADER_SIZE + data.len() + comment.len() + 1,
+			UserData::Raw(bytes) => bytes.len(),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the comment of an extended header.
+	pub fn comment(&self) -> Option<&str> {
+		match self {
+			UserData::Extended { comment, .. } => Some(comment),
+			UserData::Raw(_) => None,
+		}
+	}
+}
+
+impl Default for UserData {
+	fn default() -> Self {
+		UserData::Raw(Vec::new())
+	}
+}
@@ -0,0 +1,252 @@
+use crate::frame::Header;
+use crate::image::{ALIGNED_HEADER_SIZE, FRAME_HEADER_SIZE, HEADER_SIZE, IDENT};
+
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
+
+/// How serious a problem found by [`validate`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+	/// The file breaks the format, but decoding still works.
+	Warning,
+
+	/// Decoding fails or reads the wrong data.
+	Error,
+}
+
+/// A broken header invariant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+	InvalidIdentifier(u32),
+	InvalidAlignment(u8),
+	UnexpectedEof { needed: usize },
+	FrameCountMismatch { declared: usize, found: usize },
+	TrailingData(usize),
+	HeaderSizeTooSmall(u16),
+	TotalSizeMismatch { declared: u32, expected: u64 },
+	InvalidBppFormat(u8),
+	InvalidClutFormat(u8),
+	TrueColorAndPaletteFound,
+	ZeroClutColorCount,
+	TooManyClutColors { count: u16, bpp: u8 },
+	ClutSizeMismatch { clut_size: u32, palette_size: usize },
+	ImageSizeTooSmall { image_size: u32, needed: usize },
+	MipmapSizeMismatch { sizes: u64, image_size: u32 },
+	Misaligned { alignment: usize },
+}
+
+/// A problem found at `offset`, inside frame `frame` when it concerns one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub frame: Option<usize>,
+	pub offset: usize,
+	pub kind: DiagnosticKind,
+}
+
+struct Report {
+	frame: Option<usize>,
+	result: Vec<Diagnostic>,
+}
+
+impl Report {
+	fn push(&mut self, severity: Severity, offset: usize, kind: DiagnosticKind) {
+		self.result.push(Diagnostic {
+			severity,
+			frame: self.frame,
+			offset,
+			kind,
+		});
+	}
+}
+
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+	LittleEndian::read_u16(&buffer[offset..offset + 2])
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+	LittleEndian::read_u32(&buffer[offset..offset + 4])
+}
+
+/// Checks one frame and returns its size, or `None` when the following frames can't be found.
+fn validate_frame(buffer: &[u8], offset: usize, alignment: usize, report: &mut Report) -> Option<usize> {
+	use DiagnosticKind::*;
+	use Severity::*;
+
+	if buffer.len() < offset + FRAME_HEADER_SIZE {
+		report.push(Error, offset, UnexpectedEof { needed: offset + FRAME_HEADER_SIZE - buffer.len() });
+		return None;
+	}
+
+	let total_size = read_u32(buffer, offset);
+	let clut_size = read_u32(buffer, offset + 4);
+	let image_size = read_u32(buffer, offset + 8);
+	let header_size = read_u16(buffer, offset + 12);
+	let clut_color_count = read_u16(buffer, offset + 14);
+	let mipmap_count = buffer[offset + 17];
+	let clut_format = buffer[offset + 18];
+	let bpp_format = buffer[offset + 19];
+	let width = read_u16(buffer, offset + 20) as usize;
+	let height = read_u16(buffer, offset + 22) as usize;
+	let expected = header_size as u64 + image_size as u64 + clut_size as u64;
+	let image_offset = offset + header_size as usize;
+	let clut_offset = image_offset + image_size as usize;
+
+	if (header_size as usize) < FRAME_HEADER_SIZE {
+		report.push(Error, offset, HeaderSizeTooSmall(header_size));
+		return None;
+	}
+
+	if total_size as u64 != expected {
+		report.push(Warning, offset, TotalSizeMismatch { declared: total_size, expected });
+	}
+
+	for part in [offset, image_offset, clut_offset] {
+		if !part.is_multiple_of(alignment) {
+			report.push(Warning, part, Misaligned { alignment });
+		}
+	}
+
+	let bpp = Header::find_bpp(bpp_format).ok();
+
+	if bpp.is_none() {
+		report.push(Error, offset + 19, InvalidBppFormat(bpp_format));
+	}
+
+	if clut_size > 0 {
+		let color_size = match clut_format & 0x07 {
+			1 => Some(2),
+			2 => Some(3),
+			3 => Some(4),
+			_ => None,
+		};
+
+		if color_size.is_none() {
+			report.push(Error, offset + 18, InvalidClutFormat(clut_format));
+		}
+
+		match bpp {
+			Some(n) if n > 8 => report.push(Error, offset, TrueColorAndPaletteFound),
+			Some(n) if clut_color_count as usize > 1 << n => {
+				report.push(Error, offset + 14, TooManyClutColors { count: clut_color_count, bpp: n });
+			},
+			_ => {},
+		}
+
+		let palette_size = clut_color_count as usize * color_size.unwrap_or(0);
+
+		if clut_color_count == 0 {
+			report.push(Error, offset + 14, ZeroClutColorCount);
+		} else if palette_size > 0 && !(clut_size as usize).is_multiple_of(palette_size) {
+			let severity = if (clut_size as usize) < palette_size { Error } else { Warning };
+
+			report.push(severity, offset + 4, ClutSizeMismatch { clut_size, palette_size });
+		}
+	}
+
+	if let Some(bpp) = bpp {
+		let levels = (mipmap_count as usize).max(1);
+		let needed: usize = (0..levels)
+			.map(|level| {
+				let width = width.checked_shr(level as u32).unwrap_or(0).max(1);
+				let height = height.checked_shr(level as u32).unwrap_or(0).max(1);
+
+				(width * height * bpp as usize).div_ceil(8)
+			})
+			.sum();
+
+		if (image_size as usize) < needed {
+			report.push(Error, offset + 8, ImageSizeTooSmall { image_size, needed });
+		}
+	}
+
+	if mipmap_count > 1 {
+		let sizes_offset = offset + FRAME_HEADER_SIZE + 16;
+		let sizes_end = sizes_offset + mipmap_count as usize * 4;
+
+		if sizes_end > image_offset || sizes_end > buffer.len() {
+			report.push(Error, offset + 12, HeaderSizeTooSmall(header_size));
+		} else {
+			let sizes: u64 = (sizes_offset..sizes_end)
+				.step_by(4)
+				.map(|v| read_u32(buffer, v) as u64)
+				.sum();
+
+			if sizes != image_size as u64 {
+				let severity = if sizes > image_size as u64 { Error } else { Warning };
+
+				report.push(severity, sizes_offset, MipmapSizeMismatch { sizes, image_size });
+			}
+		}
+	}
+
+	let frame_size = (total_size as u64).max(expected) as usize;
+
+	if buffer.len() < offset + frame_size {
+		report.push(Error, offset, UnexpectedEof { needed: offset + frame_size - buffer.len() });
+		return None;
+	}
+
+	Some(frame_size)
+}
+
+/// Checks the headers of a TIM2 buffer without decoding any pixel data.
+///
+/// Every broken invariant is reported, so one call sorts a bad file by all of its causes
+/// instead of the first one a decoder runs into. An empty result means the file is sound.
+///
+/// ```
+/// let buffer = std::fs::read("../assets/test.tm2").unwrap();
+///
+/// for diagnostic in tim2::validate(&buffer) {
+///     println!("{:?} @{}: {:?}", diagnostic.severity, diagnostic.offset, diagnostic.kind);
+/// }
+/// ```
+pub fn validate(buffer: &[u8]) -> Vec<Diagnostic> {
+	use DiagnosticKind::*;
+	use Severity::*;
+
+	let mut report = Report { frame: None, result: Vec::new() };
+
+	if buffer.len() < HEADER_SIZE {
+		report.push(Error, 0, UnexpectedEof { needed: HEADER_SIZE - buffer.len() });
+		return report.result;
+	}
+
+	let identifier = BigEndian::read_u32(&buffer[0..4]);
+	let align = buffer[5];
+	let count = read_u16(buffer, 6) as usize;
+
+	if identifier != IDENT {
+		report.push(Error, 0, InvalidIdentifier(identifier));
+		return report.result;
+	}
+
+	if align > 1 {
+		report.push(Error, 5, InvalidAlignment(align));
+	}
+
+	let alignment = if align == 1 { ALIGNED_HEADER_SIZE } else { 16 };
+	let mut offset = if align == 1 { ALIGNED_HEADER_SIZE } else { HEADER_SIZE };
+
+	for i in 0..count {
+		report.frame = Some(i);
+
+		match validate_frame(buffer, offset, alignment, &mut report) {
+			Some(size) if align == 1 => offset = (offset + size).next_multiple_of(alignment),
+			Some(size) => offset += size,
+			None => {
+				report.frame = None;
+				report.push(Error, 6, FrameCountMismatch { declared: count, found: i });
+				return report.result;
+			},
+		}
+	}
+
+	report.frame = None;
+
+	if offset < buffer.len() {
+		report.push(Warning, offset, TrailingData(buffer.len() - offset));
+	}
+
+	report.result
+}
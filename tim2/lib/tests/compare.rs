@@ -0,0 +1,62 @@
+use tim2::{CompareOptions, Format, Frame, Pixel};
+
+fn read_frame(name: &str) -> Frame {
+	let buffer = std::fs::read(format!("tests/corpus/{}.tm2", name)).unwrap();
+
+	Frame::read(&buffer, &mut 16).unwrap()
+}
+
+#[test]
+fn identical_frames() {
+	let diff = tim2::compare(&read_frame("rgba8888"), &read_frame("rgba8888"));
+
+	assert!(diff.is_identical());
+	assert_eq!(diff.psnr, f64::INFINITY);
+	assert_eq!(diff.diff_image, None);
+}
+
+#[test]
+fn reencoded_frame_keeps_pixels() {
+	let original = read_frame("indexed8_clut32_csm2");
+	let (width, height) = (original.header().width(), original.header().height());
+	let reencoded = Frame::from_rgba(width, height, Format::Rgba8888, &original.to_raw(None)).unwrap();
+	let diff = tim2::compare(&original, &reencoded);
+
+	assert_eq!(diff.mismatched_pixels, 0);
+	assert!(diff.header.iter().any(|v| v.name == "bpp" && v.left == 8 && v.right == 32));
+	assert!(!diff.palettes.is_empty());
+}
+
+#[test]
+fn recolored_palette_is_reported() {
+	let original = read_frame("indexed4_clut32");
+	let mut recolored = read_frame("indexed4_clut32");
+
+	recolored.recolor_palette(0, &[Pixel::rgba(1, 2, 3, 255)]).unwrap();
+
+	let options = CompareOptions { diff_image: true };
+	let diff = tim2::compare_with_options(&original, &recolored, options);
+	let image = diff.diff_image.as_ref().unwrap();
+	let (left, right) = (original.to_raw(None), recolored.to_raw(None));
+	let expected = left.chunks_exact(4).zip(right.chunks_exact(4)).filter(|(l, r)| l != r).count();
+
+	assert_eq!(diff.palettes.len(), 1);
+	assert_eq!(diff.palettes[0].index, 0);
+	assert!(diff.header.is_empty());
+	assert!(diff.mismatched_pixels > 0);
+	assert_eq!(diff.mismatched_pixels, expected);
+	assert!(diff.psnr.is_finite());
+	assert_eq!(image.len(), diff.width as usize * diff.height as usize * 4);
+	assert_eq!(image.chunks_exact(4).filter(|v| *v == [255, 0, 0, 255]).count(), expected);
+}
+
+#[test]
+fn different_sizes_are_padded() {
+	let small = Frame::from_rgba(1, 1, Format::Rgba8888, &[9, 9, 9, 255]).unwrap();
+	let large = Frame::from_rgba(2, 1, Format::Rgba8888, &[9, 9, 9, 255, 0, 0, 0, 0]).unwrap();
+	let diff = tim2::compare(&small, &large);
+
+	assert_eq!((diff.width, diff.height), (2, 1));
+	assert_eq!(diff.mismatched_pixels, 0);
+	assert_eq!(diff.header.iter().find(|v| v.name == "width").unwrap().left, 1);
+}
@@ -0,0 +1,55 @@
+//! Handmade files, one per pixel format and CLUT mode, each with the RGBA8 image it
+//! should decode to.
+
+fn check(name: &str) {
+	let image = tim2::load(format!("tests/corpus/{}.tm2", name)).unwrap();
+	let expected = image::open(format!("tests/corpus/{}.png", name)).unwrap().to_rgba8();
+
+	assert_eq!(image.frames().len(), 1);
+	assert_eq!(image.get_frame(0).to_raw(None), expected.into_raw(), "{}", name);
+}
+
+#[test]
+fn indexed4_clut32() {
+	check("indexed4_clut32");
+}
+
+#[test]
+fn indexed4_clut16() {
+	check("indexed4_clut16");
+}
+
+#[test]
+fn indexed8_clut32_csm1() {
+	check("indexed8_clut32_csm1");
+}
+
+#[test]
+fn indexed8_clut16_csm1() {
+	check("indexed8_clut16_csm1");
+}
+
+#[test]
+fn indexed8_clut32_csm2() {
+	check("indexed8_clut32_csm2");
+}
+
+#[test]
+fn indexed8_clut16_csm2() {
+	check("indexed8_clut16_csm2");
+}
+
+#[test]
+fn abgr1555() {
+	check("abgr1555");
+}
+
+#[test]
+fn rgb888() {
+	check("rgb888");
+}
+
+#[test]
+fn rgba8888() {
+	check("rgba8888");
+}
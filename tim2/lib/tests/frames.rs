@@ -0,0 +1,143 @@
+use std::io::Cursor;
+
+/// Returns the frame bytes of a single frame file from the corpus.
+fn corpus_frame(name: &str) -> Vec<u8> {
+	std::fs::read(format!("tests/corpus/{}.tm2", name)).unwrap().split_off(16)
+}
+
+fn file_header(align: u8, count: u16) -> Vec<u8> {
+	let mut result = b"TIM2".to_vec();
+
+	result.extend_from_slice(&[4, align]);
+	result.extend_from_slice(&count.to_le_bytes());
+	result.extend_from_slice(&[0; 8]);
+	result
+}
+
+fn pad(buffer: &mut Vec<u8>, alignment: usize) {
+	buffer.resize(buffer.len().next_multiple_of(alignment), 0);
+}
+
+#[test]
+fn reads_128_byte_aligned_files() {
+	let first = corpus_frame("rgba8888");
+	let second = corpus_frame("indexed8_clut32_csm1");
+	let mut buffer = file_header(1, 2);
+
+	pad(&mut buffer, 128);
+	buffer.extend_from_slice(&first);
+	pad(&mut buffer, 128);
+
+	let second_offset = buffer.len() as u64;
+
+	buffer.extend_from_slice(&second);
+	pad(&mut buffer, 128);
+
+	let image = tim2::from_buffer(&buffer).unwrap();
+	let expected = tim2::load("tests/corpus/indexed8_clut32_csm1.tm2").unwrap();
+
+	assert_eq!(image.align(), 1);
+	assert_eq!(image.frames().len(), 2);
+	assert_eq!(image.get_frame(1).to_raw(None), expected.get_frame(0).to_raw(None));
+	assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+
+	let stream = tim2::read_from(Cursor::new(&buffer)).unwrap();
+
+	assert_eq!(stream.frames()[0].offset(), 128);
+	assert_eq!(stream.frames()[1].offset(), second_offset);
+
+	// the corpus frames don't pad their headers and image data to 128 bytes
+	for diagnostic in tim2::validate(&buffer) {
+		assert!(matches!(diagnostic.kind, tim2::DiagnosticKind::Misaligned { alignment: 128 }));
+	}
+}
+
+#[test]
+fn finds_frames_by_declared_size() {
+	let mut first = corpus_frame("rgb888");
+	let total_size = u32::from_le_bytes([first[0], first[1], first[2], first[3]]) + 32;
+
+	first[0..4].copy_from_slice(&total_size.to_le_bytes());
+	first.extend_from_slice(&[0xFF; 32]);
+
+	let mut buffer = file_header(0, 2);
+
+	buffer.extend_from_slice(&first);
+	buffer.extend_from_slice(&corpus_frame("abgr1555"));
+
+	let image = tim2::from_buffer(&buffer).unwrap();
+	let inspection = tim2::inspect(&buffer).unwrap();
+
+	assert_eq!(image.get_frame(1).header().width(), 16);
+	assert_eq!(inspection.frames[1].offset, 16 + total_size as u64);
+}
+
+#[test]
+fn rejects_frames_past_the_end() {
+	let mut buffer = std::fs::read("tests/corpus/rgb888.tm2").unwrap();
+	let total_size = u32::from_le_bytes([buffer[16], buffer[17], buffer[18], buffer[19]]) + 1;
+
+	buffer[16..20].copy_from_slice(&total_size.to_le_bytes());
+
+	assert!(matches!(tim2::from_buffer(&buffer), Err(tim2::Error::UnexpectedEof { .. })));
+}
+
+#[test]
+fn streams_report_truncated_files_like_buffers() {
+	let buffer = std::fs::read("tests/corpus/rgb888.tm2").unwrap();
+
+	// the header of the file, the header of the frame, and its pixels
+	for (len, offset, needed) in [(10, 0, 16), (16 + 20, 16, 48), (buffer.len() - 1, 64, buffer.len() - 64)] {
+		let truncated = &buffer[..len];
+		let streamed = tim2::read_from(Cursor::new(truncated)).and_then(|mut v| v.decode(0).unwrap());
+		let read = tim2::from_buffer(truncated);
+
+		assert!(matches!(streamed, Err(tim2::Error::UnexpectedEof { offset: a, needed: b }) if (a, b) == (offset, needed)), "{}", len);
+		assert!(matches!(read, Err(tim2::Error::UnexpectedEof { .. })), "{}", len);
+	}
+}
+
+#[test]
+fn from_rgba_builds_palettes_in_order_of_appearance() {
+	let colors: Vec<[u8; 4]> = (0..17u8).map(|i| [i * 15, 0, 255 - i, 255]).collect();
+	let rgba: Vec<u8> = colors[..16].iter().rev().chain(&colors[..16]).flatten().copied().collect();
+	let frame = tim2::Frame::from_rgba(8, 4, tim2::Format::Indexed4, &rgba).unwrap();
+
+	assert_eq!(frame.palettes()[0], colors[..16].iter().rev().map(|v| tim2::Pixel::from(*v)).collect::<Vec<_>>());
+	assert_eq!(frame.to_raw(None), rgba);
+
+	let rgba: Vec<u8> = colors.iter().flatten().copied().cycle().take(17 * 4 * 4).collect();
+
+	assert!(matches!(tim2::Frame::from_rgba(17, 4, tim2::Format::Indexed4, &rgba), Err(tim2::Error::TooManyColors(17))));
+}
+
+#[test]
+fn from_rgba_stops_at_the_first_color_past_the_palette() {
+	let rgba: Vec<u8> = (0..512u32 * 512).flat_map(|i| [i as u8, (i >> 8) as u8, (i >> 16) as u8, 255]).collect();
+
+	assert!(matches!(tim2::Frame::from_rgba(512, 512, tim2::Format::Indexed8, &rgba), Err(tim2::Error::TooManyColors(257))));
+}
+
+#[test]
+fn rejects_indexed_frames_without_clut() {
+	let mut buffer = std::fs::read("tests/corpus/rgba8888.tm2").unwrap();
+
+	buffer[16 + 19] = 4; // 4 bits per pixel, but no CLUT to index
+
+	assert!(matches!(tim2::from_buffer(&buffer), Err(tim2::Error::InvalidPixelSize(0))));
+}
+
+#[test]
+fn rejects_mipmaps_larger_than_the_image_data() {
+	let mut buffer = std::fs::read("tests/corpus/indexed8_mip3.tm2").unwrap();
+	let sizes = 16 + 48 + 16;
+
+	// level 2 grows from 32 to 64 bytes, past the 672 bytes of image data
+	buffer[sizes + 8..sizes + 12].copy_from_slice(&64u32.to_le_bytes());
+
+	let err = tim2::from_buffer(&buffer).unwrap_err();
+
+	assert!(matches!(err, tim2::Error::MipmapSizesTooLarge(704, 672)));
+	assert_eq!(err.kind(), "MipmapSizesTooLarge");
+	assert_eq!(err.to_string(), "mipmap levels take 704 bytes, more than the 672 bytes of image data");
+}
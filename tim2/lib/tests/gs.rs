@@ -0,0 +1,73 @@
+use tim2::{Csm, Filter, MipTbp, Psm, Tex0, Tex1, TexClut, TextureFunction};
+
+#[test]
+fn tex_0_round_trips() {
+	// TH spans bits 30 to 33, across the two 32-bit halves of the register
+	let raw = 0x8390_800D_9D31_0ABC;
+	let tex_0 = Tex0 {
+		tbp0: 0x0ABC,
+		tbw: 4,
+		psm: Psm::Psmt8,
+		tw: 7,
+		th: 6,
+		tcc: true,
+		tfx: TextureFunction::Decal,
+		cbp: 0x0400,
+		cpsm: Psm::Psmct16,
+		csm: Csm::Csm2,
+		csa: 3,
+		cld: 4,
+	};
+
+	assert_eq!(Tex0::from_raw(raw), tex_0);
+	assert_eq!(tex_0.to_raw(), raw);
+	assert_eq!((tex_0.width(), tex_0.height()), (128, 64));
+}
+
+#[test]
+fn tex_0_keeps_unknown_storage_modes() {
+	let raw = 0x3F80_0000;
+	let tex_0 = Tex0::from_raw(raw);
+
+	assert_eq!(tex_0.psm, Psm::Other(0x38));
+	assert_eq!(tex_0.to_raw(), raw);
+}
+
+#[test]
+fn tex_1_round_trips() {
+	let raw = 0x0FE8_0010_032D;
+	let tex_1 = Tex1 {
+		lcm: true,
+		mxl: 3,
+		mmag: Filter::Linear,
+		mmin: Filter::LinearMipmapNearest,
+		mtba: true,
+		l: 2,
+		k: -24,
+	};
+
+	assert_eq!(Tex1::from_raw(raw), tex_1);
+	assert_eq!(tex_1.to_raw(), raw);
+	assert_eq!(tex_1.k_value(), -1.5);
+}
+
+#[test]
+fn tex_clut_round_trips() {
+	let raw = 0x0015_50C5;
+	let tex_clut = TexClut { cbw: 5, cou: 3, cov: 0x155 };
+
+	assert_eq!(TexClut::from_raw(raw), tex_clut);
+	assert_eq!(tex_clut.to_raw(), raw);
+}
+
+#[test]
+fn mip_tbp_round_trips() {
+	let raw = 0x0090_0010_200F_FFFF;
+	let mip_tbp = MipTbp {
+		tbp: [0x3FFF, 0x0200, 0x1000],
+		tbw: [0x3F, 4, 2],
+	};
+
+	assert_eq!(MipTbp::from_raw(raw), mip_tbp);
+	assert_eq!(mip_tbp.to_raw(), raw);
+}
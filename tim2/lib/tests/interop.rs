@@ -0,0 +1,79 @@
+#![cfg(feature = "image")]
+
+use image::{DynamicImage, RgbaImage};
+use tim2::{Format, Frame, Pixel, QuantizeOptions};
+
+fn load(name: &str) -> tim2::Image {
+	tim2::load(format!("tests/corpus/{}.tm2", name)).unwrap()
+}
+
+fn round_trip(name: &str, format: Format) {
+	let image = load(name);
+	let frame = image.get_frame(0);
+	let rgba = frame.to_rgba_image(None);
+	let expected = image::open(format!("tests/corpus/{}.png", name)).unwrap().to_rgba8();
+
+	assert_eq!(rgba, expected, "{}", name);
+	assert_eq!(RgbaImage::from(frame), expected, "{}", name);
+
+	let result = Frame::from_image(&DynamicImage::ImageRgba8(rgba), format, QuantizeOptions::default()).unwrap();
+
+	assert_eq!(result.header().pixel_format().unwrap(), format, "{}", name);
+	assert_eq!(result.to_rgba_image(None), expected, "{}", name);
+}
+
+#[test]
+fn indexed_frames_round_trip() {
+	round_trip("indexed4_clut32", Format::Indexed4);
+	round_trip("indexed8_clut32_csm1", Format::Indexed8);
+	round_trip("indexed8_clut16_csm2", Format::Indexed8);
+}
+
+#[test]
+fn direct_color_frames_round_trip() {
+	round_trip("abgr1555", Format::Abgr1555);
+	round_trip("rgb888", Format::Rgb888);
+	round_trip("rgba8888", Format::Rgba8888);
+}
+
+#[test]
+fn converts_mip_levels_and_palettes() {
+	let image = tim2::load("tests/corpus/indexed8_mip3.tm2").unwrap();
+	let frame = image.get_frame(0);
+
+	for (level, size) in [(0, (32, 16)), (1, (16, 8)), (2, (8, 4))] {
+		let rgba = frame.mip_level_to_rgba_image(level, None).unwrap();
+
+		assert_eq!(rgba.dimensions(), size);
+		assert_eq!(rgba.into_raw(), frame.mip_level_to_raw(level, None).unwrap());
+	}
+
+	assert!(frame.mip_level_to_rgba_image(3, None).is_none());
+	assert_eq!(frame.to_rgba_image_with_palette(0, None).unwrap(), frame.to_rgba_image(None));
+	assert!(matches!(frame.to_rgba_image_with_palette(1, None), Err(tim2::Error::InvalidPaletteIndex(1))));
+}
+
+#[test]
+fn template_round_trips_through_rgba_image() {
+	for name in ["indexed4_clut16", "indexed8_clut32_csm1", "rgba8888"] {
+		let buffer = std::fs::read(format!("tests/corpus/{}.tm2", name)).unwrap();
+		let mut image = tim2::from_buffer(&buffer).unwrap();
+		let rgba = image.get_frame(0).to_rgba_image(Some(tim2::COLOR_KEY));
+		let frame = Frame::from_image_with_template(image.get_frame(0), &DynamicImage::ImageRgba8(rgba), Some(tim2::COLOR_KEY)).unwrap();
+
+		*image.get_frame_mut(0) = frame;
+		assert_eq!(tim2::to_buffer(&image).unwrap(), buffer, "{}", name);
+	}
+}
+
+#[test]
+fn swatches_round_trip() {
+	let palette: Vec<Pixel> = (0..20).map(|i| Pixel::rgba(i * 10, 255 - i, i, 128 + i)).collect();
+	let swatch = tim2::palette_to_swatch(&palette);
+	let result = tim2::swatch_to_palette(&swatch);
+
+	assert_eq!(swatch.dimensions(), (16, 2));
+	assert_eq!(result[..20], palette[..]);
+	assert!(result[20..].iter().all(|v| *v == Pixel::rgba(0, 0, 0, 0)));
+	assert_eq!(tim2::palette_to_swatch(&[]).dimensions(), (16, 1));
+}
@@ -0,0 +1,66 @@
+//! `indexed8_mip3.tm2` is a handmade 32x16 frame with two mipmap levels, a 28 byte
+//! mipmap header padded to 32, and linear texels. The texel at `x, y` of level `n` holds
+//! palette index `n * 64 + y * 8 + x`, and palette entry `i` is `(i, 255 - i, i / 2, 255)`.
+
+use tim2::Pixel;
+
+const PATH: &str = "tests/corpus/indexed8_mip3.tm2";
+
+fn expected_level(level: usize) -> Vec<Pixel> {
+	let (width, height) = (32 >> level, 16 >> level);
+	let positions = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+	positions
+		.map(|(x, y)| {
+			let i = ((level * 64 + y * 8 + x) & 0xFF) as u8;
+
+			Pixel::rgba(i, 255 - i, i / 2, 255)
+		})
+		.collect()
+}
+
+#[test]
+fn reads_every_level() {
+	let image = tim2::load(PATH).unwrap();
+	let frame = image.get_frame(0);
+	let header = frame.header();
+
+	assert_eq!(header.level_count(), 3);
+	assert_eq!(header.header_size(), 80);
+	assert_eq!(header.mipmap_sizes(), &vec![512, 128, 32]);
+	assert_eq!(header.level_size(1), (16, 8));
+	assert_eq!(header.level_size(2), (8, 4));
+	assert_eq!(header.gs_miptbp_1(), 0x0000_1234_0008_0100);
+	assert_eq!(header.gs_miptbp_2(), 0x40);
+
+	let levels = frame.mip_levels();
+
+	assert_eq!(levels.len(), 3);
+
+	for (level, pixels) in levels.iter().enumerate() {
+		assert_eq!(pixels, &expected_level(level), "level {}", level);
+	}
+}
+
+#[test]
+fn converts_each_level_to_rgba() {
+	let image = tim2::load(PATH).unwrap();
+	let frame = image.get_frame(0);
+
+	for level in 0..3 {
+		let expected: Vec<u8> = expected_level(level).iter().flat_map(|v| <[u8; 4]>::from(*v)).collect();
+
+		assert_eq!(frame.mip_level_to_raw(level, None), Some(expected));
+	}
+
+	assert_eq!(frame.mip_level_to_raw(3, None), None);
+}
+
+#[test]
+fn round_trips_mipmaps_and_header_padding() {
+	let buffer = std::fs::read(PATH).unwrap();
+	let image = tim2::from_buffer(&buffer).unwrap();
+
+	assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+	assert!(tim2::validate(&buffer).is_empty());
+}
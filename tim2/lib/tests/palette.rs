@@ -0,0 +1,116 @@
+use tim2::{ClutFormat, Format, Frame, PaletteFormat, Pixel};
+
+fn read_frame(name: &str) -> Frame {
+	let buffer = std::fs::read(format!("tests/corpus/{}.tm2", name)).unwrap();
+
+	Frame::read(&buffer, &mut 16).unwrap()
+}
+
+fn opaque(palette: &[Pixel]) -> Vec<Pixel> {
+	palette.iter().map(|v| Pixel::rgba(v.r(), v.g(), v.b(), 255)).collect()
+}
+
+#[test]
+fn formats_round_trip() {
+	let frame = read_frame("indexed8_clut32_csm1");
+	let palette = &frame.palettes()[0];
+
+	for format in [PaletteFormat::Jasc, PaletteFormat::Gimp, PaletteFormat::Act] {
+		let bytes = tim2::export_palette(palette, "test", format);
+
+		assert_eq!(tim2::import_palette(&bytes, format).unwrap(), opaque(palette), "{:?}", format);
+	}
+}
+
+#[test]
+fn malformed_palettes_are_rejected() {
+	let jasc = b"JASC-PAL\r\n0100\r\n2\r\n1 2 3\r\n4 5\r\n";
+	let gimp = b"GIMP Palette\nName: x\n#\n1 2 300\tIndex 0\n";
+
+	assert!(matches!(tim2::import_palette(jasc, PaletteFormat::Jasc), Err(tim2::Error::MalformedPalette(5))));
+	assert!(matches!(tim2::import_palette(gimp, PaletteFormat::Gimp), Err(tim2::Error::MalformedPalette(4))));
+	assert!(tim2::import_palette(&[0; 10], PaletteFormat::Act).is_err());
+}
+
+#[test]
+fn set_palette_changes_decoded_pixels() {
+	let mut frame = read_frame("indexed4_clut32");
+	let palette = vec![Pixel::rgba(10, 20, 30, 255); 3];
+
+	frame.set_palette(0, palette).unwrap();
+	assert!(frame.set_palette(0, vec![Pixel::rgba(0, 0, 0, 0); 17]).is_err());
+
+	let mut buffer = Vec::new();
+
+	frame.write(&mut buffer).unwrap();
+
+	let frame = Frame::read(&buffer, &mut 0).unwrap();
+	let palette = &frame.palettes()[0];
+
+	assert_eq!(palette.len(), frame.header().clut_color_count() as usize);
+	assert_eq!(palette[0], Pixel::rgba(10, 20, 30, 255));
+	assert_eq!(palette[15], Pixel::rgba(0, 0, 0, 0));
+}
+
+#[test]
+fn recolor_palette_keeps_alpha() {
+	let mut frame = read_frame("indexed8_clut32_csm2");
+	let alpha: Vec<u8> = frame.palettes()[0].iter().map(|v| v.a()).collect();
+	let colors = vec![Pixel::rgba(1, 2, 3, 255); alpha.len()];
+
+	frame.recolor_palette(0, &colors).unwrap();
+
+	for (pixel, a) in frame.palettes()[0].iter().zip(alpha) {
+		assert_eq!(*pixel, Pixel::rgba(1, 2, 3, a));
+	}
+}
+
+/// A 4x1 frame with two 16-color palettes, the second one reversing the first.
+fn two_palettes() -> Frame {
+	let first: Vec<Pixel> = (0..16).map(|i| Pixel::rgba(i * 16, 0, 255 - i * 16, 255)).collect();
+	let second = first.iter().rev().copied().collect();
+
+	Frame::from_indexed(4, 1, Format::Indexed4, &[0, 1, 2, 15], vec![first, second], ClutFormat::default()).unwrap()
+}
+
+#[test]
+fn decodes_with_any_palette() {
+	let frame = two_palettes();
+	let palettes = frame.palettes();
+	let expected: Vec<Pixel> = [15, 14, 13, 0].iter().map(|v| palettes[0][*v]).collect();
+	let raw: Vec<u8> = expected.iter().flat_map(|v| <[u8; 4]>::from(*v)).collect();
+
+	assert_eq!(frame.header().clut_color_count(), 16);
+	assert_eq!(frame.get_pixels_with_palette(0).unwrap(), frame.get_pixels());
+	assert_eq!(frame.get_pixels_with_palette(1).unwrap(), expected);
+	assert_eq!(frame.to_raw_with_palette(1, None).unwrap(), raw);
+}
+
+#[test]
+fn set_palette_keeps_the_other_palettes() {
+	let mut frame = two_palettes();
+	let first = frame.palettes()[0].clone();
+
+	frame.set_palette(1, vec![Pixel::rgba(1, 2, 3, 255)]).unwrap();
+
+	let mut buffer = Vec::new();
+
+	frame.write(&mut buffer).unwrap();
+
+	let frame = Frame::read(&buffer, &mut 0).unwrap();
+
+	assert_eq!(frame.palettes().len(), 2);
+	assert_eq!(frame.palettes()[0], first);
+	assert_eq!(frame.get_pixels_with_palette(1).unwrap()[0], Pixel::rgba(1, 2, 3, 255));
+	assert_eq!(frame.get_pixels_with_palette(1).unwrap()[1], Pixel::rgba(0, 0, 0, 0));
+}
+
+#[test]
+fn rejects_palette_indices_past_the_clut() {
+	let mut frame = two_palettes();
+
+	assert!(matches!(frame.get_pixels_with_palette(2), Err(tim2::Error::InvalidPaletteIndex(2))));
+	assert!(matches!(frame.to_raw_with_palette(2, None), Err(tim2::Error::InvalidPaletteIndex(2))));
+	assert!(matches!(frame.set_palette(2, Vec::new()), Err(tim2::Error::InvalidPaletteIndex(2))));
+	assert!(matches!(frame.recolor_palette(2, &[]), Err(tim2::Error::InvalidPaletteIndex(2))));
+}
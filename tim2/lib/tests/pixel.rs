@@ -0,0 +1,66 @@
+use tim2::{AlphaMode, ClutFormat, Color1555, DecodeOptions, Format, Frame, Pixel};
+
+#[test]
+fn color_1555_round_trips() {
+	for raw in 0..=u16::MAX {
+		let pixel: Pixel = Color1555(raw).into();
+
+		assert_eq!(Color1555::from(pixel), Color1555(raw));
+	}
+}
+
+#[test]
+fn color_1555_is_little_endian() {
+	let pixel = Pixel::from_buf(&[0xE0, 0x83]).unwrap();
+
+	assert_eq!(pixel, Pixel::rgba(0, 255, 0, 255));
+	assert_eq!(pixel.to_bytes(Format::Abgr1555).unwrap(), vec![0xE0, 0x83]);
+}
+
+#[test]
+fn rgba_packings_agree() {
+	let pixel = Pixel::rgba(0x12, 0x34, 0x56, 0x78);
+
+	assert_eq!(u32::from(pixel), 0x1234_5678);
+	assert_eq!(<[u8; 4]>::from(pixel), [0x12, 0x34, 0x56, 0x78]);
+	assert_eq!(Pixel::from(0x1234_5678), pixel);
+	assert_eq!(Pixel::from([0x12, 0x34, 0x56, 0x78]), pixel);
+	assert_eq!(pixel.to_bytes(Format::Rgba8888).unwrap(), vec![0x12, 0x34, 0x56, 0x78]);
+}
+
+#[test]
+fn indexed_formats_have_no_color_bytes() {
+	assert!(Pixel::new().to_bytes(Format::Indexed8).is_err());
+}
+
+const ALPHAS: [u8; 4] = [0x00, 0x40, 0x80, 0xFF];
+const SCALED: [u8; 4] = [0x00, 0x80, 0xFF, 0xFF];
+
+fn alphas(raw: &[u8]) -> Vec<u8> {
+	raw.chunks(4).map(|v| v[3]).collect()
+}
+
+#[test]
+fn ps2_alpha_scales_to_full_range() {
+	for (alpha, expected) in ALPHAS.iter().zip(SCALED) {
+		assert_eq!(AlphaMode::Ps2Scaled.apply(*alpha), expected);
+		assert_eq!(AlphaMode::Raw.apply(*alpha), *alpha);
+	}
+}
+
+#[test]
+fn decode_options_apply_to_every_conversion() {
+	let scaled = DecodeOptions { alpha: AlphaMode::Ps2Scaled };
+	let rgba: Vec<u8> = ALPHAS.iter().flat_map(|v| [10, 20, 30, *v]).collect();
+	let frame = Frame::from_rgba(4, 1, Format::Rgba8888, &rgba).unwrap();
+
+	assert_eq!(alphas(&frame.to_raw(None)), ALPHAS);
+	assert_eq!(alphas(&frame.to_raw_with_options(None, scaled)), SCALED);
+	assert_eq!(alphas(&frame.mip_level_to_raw_with_options(0, None, scaled).unwrap()), SCALED);
+
+	let palette: Vec<Pixel> = ALPHAS.iter().map(|v| Pixel::rgba(10, 20, 30, *v)).collect();
+	let frame = Frame::from_indexed(4, 1, Format::Indexed4, &[0, 1, 2, 3], vec![vec![], palette], ClutFormat::default()).unwrap();
+
+	assert_eq!(alphas(&frame.to_raw_with_palette_and_options(1, None, scaled).unwrap()), SCALED);
+	assert_eq!(alphas(&frame.to_raw_with_palette(1, None).unwrap()), ALPHAS);
+}
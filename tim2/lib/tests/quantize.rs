@@ -0,0 +1,86 @@
+use tim2::{Pixel, QuantizeOptions, COLOR_KEY};
+
+/// A 64x64 image with 4096 distinct colors.
+fn gradient() -> Vec<u8> {
+	(0..64u8).flat_map(|y| (0..64u8).flat_map(move |x| [x * 4, y * 4, x ^ y, 255])).collect()
+}
+
+fn colors(rgba: &[u8]) -> Vec<Pixel> {
+	rgba.chunks(4).map(|v| Pixel::rgba(v[0], v[1], v[2], v[3])).collect()
+}
+
+#[test]
+fn caps_the_palette_size() {
+	let rgba = gradient();
+
+	for color_count in [16, 256] {
+		let result = tim2::quantize(64, 64, &rgba, color_count, QuantizeOptions::default()).unwrap();
+
+		assert_eq!(result.palette.len(), color_count);
+		assert_eq!(result.indices.len(), 64 * 64);
+		assert!(result.indices.iter().all(|v| (*v as usize) < color_count));
+	}
+
+	assert!(matches!(
+		tim2::quantize(64, 64, &rgba, 257, QuantizeOptions::default()),
+		Err(tim2::Error::InvalidColorCount(257))
+	));
+}
+
+#[test]
+fn reserves_index_0_for_the_color_key() {
+	let mut rgba = gradient();
+
+	// the key, and a color next to it that must not be merged with it
+	rgba[0..4].copy_from_slice(&<[u8; 4]>::from(COLOR_KEY));
+	rgba[4..8].copy_from_slice(&[0, 250, 0, 255]);
+
+	for dither in [false, true] {
+		let options = QuantizeOptions { dither, ..QuantizeOptions::default() };
+		let result = tim2::quantize(64, 64, &rgba, 16, options).unwrap();
+
+		assert_eq!(result.palette[0], COLOR_KEY);
+		assert_eq!(result.indices[0], 0);
+		assert!(result.indices[1..].iter().all(|v| *v != 0), "dither: {}", dither);
+		assert!(!result.palette[1..].contains(&COLOR_KEY));
+	}
+}
+
+/// A 64x64 image with unevenly sized groups of colors that share a channel value.
+fn ties() -> Vec<u8> {
+	(0..64u8).flat_map(|y| (0..64u8).flat_map(move |x| [(x ^ y) % 7 * 40, (x + y) % 5 * 60, x % 3 * 100, y / 16 * 80])).collect()
+}
+
+#[test]
+fn dithering_is_deterministic() {
+	for rgba in [gradient(), ties()] {
+		let options = QuantizeOptions { dither: true, color_key: None };
+		let first = tim2::quantize(64, 64, &rgba, 16, options).unwrap();
+		let plain = tim2::quantize(64, 64, &rgba, 16, QuantizeOptions { dither: false, color_key: None }).unwrap();
+
+		// the histogram is hashed differently on every run, which must not change the result
+		for _ in 0..20 {
+			let other = tim2::quantize(64, 64, &rgba, 16, options).unwrap();
+
+			assert_eq!(first.indices, other.indices);
+			assert_eq!(first.palette, other.palette);
+		}
+
+		assert_ne!(first.indices, plain.indices);
+	}
+}
+
+#[test]
+fn keeps_exact_colors_that_fit() {
+	let palette = [Pixel::rgba(255, 0, 0, 255), Pixel::rgba(0, 0, 255, 128), Pixel::rgba(9, 9, 9, 0)];
+	let rgba: Vec<u8> = (0..16).flat_map(|i| <[u8; 4]>::from(palette[i % 3])).collect();
+
+	for dither in [false, true] {
+		let options = QuantizeOptions { dither, ..QuantizeOptions::default() };
+		let result = tim2::quantize(4, 4, &rgba, 16, options).unwrap();
+		let pixels: Vec<Pixel> = result.indices.iter().map(|v| result.palette[*v as usize]).collect();
+
+		assert_eq!(result.palette.len(), 3);
+		assert_eq!(pixels, colors(&rgba));
+	}
+}
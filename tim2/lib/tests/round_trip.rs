@@ -0,0 +1,115 @@
+//! Property tests: encoded frames decode to what they were built from, and no input makes
+//! the loader panic.
+
+use proptest::prelude::*;
+use tim2::{ClutFormat, Color1555, DataKind, Format, Frame, Image, Pixel};
+
+fn reload(frame: Frame) -> (Vec<u8>, Image) {
+	let buffer = tim2::to_buffer(&Image::new(vec![frame])).unwrap();
+	let image = tim2::from_buffer(&buffer).unwrap();
+
+	(buffer, image)
+}
+
+fn dimensions() -> impl Strategy<Value = (u16, u16)> {
+	(1u16..48, 1u16..48)
+}
+
+fn pixel(format: Format) -> impl Strategy<Value = Pixel> {
+	any::<[u8; 4]>().prop_map(move |v| match format {
+		// only colors the format can hold survive a round trip
+		Format::Abgr1555 => Color1555(u16::from_le_bytes([v[0], v[1]])).into(),
+		Format::Rgb888 => Pixel::rgba(v[0], v[1], v[2], 255),
+		_ => v.into(),
+	})
+}
+
+fn indexed_format() -> impl Strategy<Value = Format> {
+	prop_oneof![Just(Format::Indexed4), Just(Format::Indexed8)]
+}
+
+fn true_color_format() -> impl Strategy<Value = Format> {
+	prop_oneof![Just(Format::Abgr1555), Just(Format::Rgb888), Just(Format::Rgba8888)]
+}
+
+fn clut_format() -> impl Strategy<Value = ClutFormat> {
+	(prop_oneof![Just(Format::Abgr1555), Just(Format::Rgba8888)], any::<bool>())
+		.prop_map(|(color, linear)| ClutFormat { color, linear })
+}
+
+fn indexed_frame() -> impl Strategy<Value = (u16, u16, Format, Vec<u8>, Vec<Vec<Pixel>>, ClutFormat)> {
+	(dimensions(), indexed_format(), 1usize..4, clut_format()).prop_flat_map(|((width, height), format, count, clut)| {
+		let color_count = 1usize << format.bpp();
+		let indices = prop::collection::vec(any::<u8>(), width as usize * height as usize)
+			.prop_map(move |v| v.into_iter().map(|i| (i as usize % color_count) as u8).collect());
+		let palette = prop::collection::vec(pixel(clut.color), color_count);
+
+		(Just(width), Just(height), Just(format), indices, prop::collection::vec(palette, count), Just(clut))
+	})
+}
+
+fn true_color_frame() -> impl Strategy<Value = (u16, u16, Format, Vec<Pixel>)> {
+	(dimensions(), true_color_format()).prop_flat_map(|((width, height), format)| {
+		let pixels = prop::collection::vec(pixel(format), width as usize * height as usize);
+
+		(Just(width), Just(height), Just(format), pixels)
+	})
+}
+
+proptest! {
+	#[test]
+	fn indexed_frames_round_trip((width, height, format, indices, palettes, clut) in indexed_frame()) {
+		let frame = Frame::from_indexed(width, height, format, &indices, palettes.clone(), clut).unwrap();
+		let (buffer, image) = reload(frame);
+		let frame = image.get_frame(0);
+
+		match frame.data() {
+			DataKind::Indices(v) => prop_assert_eq!(v, &indices),
+			DataKind::Pixels(_) => prop_assert!(false, "expected indices"),
+		}
+
+		prop_assert_eq!(frame.palettes(), &palettes);
+		prop_assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+	}
+
+	#[test]
+	fn true_color_frames_round_trip((width, height, format, pixels) in true_color_frame()) {
+		let rgba: Vec<u8> = pixels.iter().flat_map(|v| <[u8; 4]>::from(*v)).collect();
+		let frame = Frame::from_rgba(width, height, format, &rgba).unwrap();
+		let (buffer, image) = reload(frame);
+
+		prop_assert_eq!(image.get_frame(0).to_raw(None), rgba);
+		prop_assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+	}
+
+	#[test]
+	fn arbitrary_bytes_never_panic(buffer in prop::collection::vec(any::<u8>(), 0..512)) {
+		if let Ok(image) = tim2::from_buffer(&buffer) {
+			for frame in image.frames() {
+				frame.to_raw(None);
+			}
+		}
+
+		tim2::validate(&buffer);
+		let _ = tim2::inspect(&buffer);
+	}
+
+	#[test]
+	fn corrupted_files_never_panic(edits in prop::collection::vec((0usize..256, any::<u8>()), 1..8), len in 16usize..2048) {
+		let mut buffer = std::fs::read("tests/corpus/indexed8_clut32_csm1.tm2").unwrap();
+
+		for (offset, value) in edits {
+			let offset = offset % buffer.len();
+
+			buffer[offset] = value;
+		}
+
+		buffer.truncate(len);
+
+		if let Ok(image) = tim2::from_buffer(&buffer) {
+			for frame in image.frames() {
+				frame.to_raw(None);
+			}
+		}
+	}
+}
@@ -0,0 +1,100 @@
+use tim2::{ClutFormat, Format, Frame, Image, Pixel, Psm, Storage, Tex0};
+
+fn tex_0(psm: Psm, tbw: u8, tw: u8, th: u8) -> Tex0 {
+	let mut result = Tex0::from_raw(0);
+
+	result.psm = psm;
+	result.tbw = tbw;
+	result.tw = tw;
+	result.th = th;
+	result
+}
+
+#[test]
+fn detects_psp_swizzle() {
+	// PSP files keep a float or zero in TEX0
+	assert_eq!(Storage::detect(&Tex0::from_raw(0x3F80_0000), 8, 64, 64), Storage::Swizzled);
+	assert_eq!(Storage::detect(&Tex0::from_raw(0), 4, 64, 64), Storage::Swizzled);
+}
+
+#[test]
+fn detects_linear_gs_layout() {
+	assert_eq!(Storage::detect(&tex_0(Psm::Psmt8, 2, 7, 6), 8, 128, 64), Storage::Linear);
+	assert_eq!(Storage::detect(&tex_0(Psm::Psmt4, 1, 6, 6), 4, 64, 64), Storage::Linear);
+	assert_eq!(Storage::detect(&tex_0(Psm::Psmct32, 1, 5, 5), 32, 32, 32), Storage::Linear);
+	assert_eq!(Storage::detect(&Tex0::from_raw(0), 24, 32, 32), Storage::Linear);
+}
+
+#[test]
+fn detects_interlaced_gs_layout() {
+	assert_eq!(Storage::detect(&tex_0(Psm::Psmct32, 1, 6, 6), 8, 64, 64), Storage::Interlaced);
+
+	// too small for the declared buffer, so the registers can't describe this image
+	assert_eq!(Storage::detect(&tex_0(Psm::Psmct32, 1, 5, 5), 8, 64, 64), Storage::Swizzled);
+}
+
+#[test]
+fn ms_000_matches_golden_image() {
+	let image = tim2::load("../../unpacker/assets/ms_000.tm2").unwrap();
+	let frame = image.get_frame(0);
+	let expected = image::open("tests/golden/ms_000.png").unwrap().to_rgba8();
+
+	assert_eq!(frame.header().storage(), Storage::Swizzled);
+	assert_eq!(frame.to_raw(None), expected.into_raw());
+}
+
+#[test]
+fn unswizzles_4bpp_monster_sprites() {
+	// sprites from 00ta_mon that came out distorted when swizzle blocks were 16 texels wide
+	for name in ["ms_284", "msa_314"] {
+		let image = tim2::load(format!("tests/golden/{}.tm2", name)).unwrap();
+		let frame = image.get_frame(0);
+		let expected = image::open(format!("tests/golden/{}.png", name)).unwrap().to_rgba8();
+
+		assert_eq!(frame.header().bpp(), 4, "{}", name);
+		assert_eq!(frame.header().storage(), Storage::Swizzled, "{}", name);
+		assert_eq!(frame.to_raw(None), expected.into_raw(), "{}", name);
+	}
+}
+
+/// Writes a 64x16 frame in `format` and returns its first swizzle block, along with the
+/// first 16 bytes of each of its first 8 rows.
+fn first_block(format: Format) -> (Vec<u8>, Vec<u8>) {
+	let color = |x: usize, y: usize| Pixel::rgba((x * 4) as u8, (y * 16) as u8, 0, 255);
+	let index = |x: usize, y: usize| ((x + y) % 16) as u8;
+	let frame = if format.is_indexed() {
+		let indices: Vec<u8> = (0..16).flat_map(|y| (0..64).map(move |x| index(x, y))).collect();
+		let palette = (0..16).map(|i| Pixel::rgba(i * 16, 0, 0, 255)).collect();
+
+		Frame::from_indexed(64, 16, format, &indices, vec![palette], ClutFormat::default()).unwrap()
+	} else {
+		let rgba: Vec<u8> = (0..16).flat_map(|y| (0..64).flat_map(move |x| <[u8; 4]>::from(color(x, y)))).collect();
+
+		Frame::from_rgba(64, 16, format, &rgba).unwrap()
+	};
+
+	assert_eq!(frame.header().storage(), Storage::Swizzled);
+
+	let texels = 16 * 8 / format.bpp() as usize;
+	let row = |y: usize| -> Vec<u8> {
+		match format {
+			Format::Indexed4 => (0..texels).step_by(2).map(|x| index(x + 1, y) << 4 | index(x, y)).collect(),
+			Format::Indexed8 => (0..texels).map(|x| index(x, y)).collect(),
+			_ => (0..texels).flat_map(|x| color(x, y).to_bytes(format).unwrap()).collect(),
+		}
+	};
+
+	let buffer = tim2::to_buffer(&Image::new(vec![frame])).unwrap();
+	let image_offset = 16 + 48;
+
+	(buffer[image_offset..image_offset + 128].to_vec(), (0..8).flat_map(row).collect())
+}
+
+#[test]
+fn swizzle_blocks_are_16_bytes_wide() {
+	for format in [Format::Indexed4, Format::Indexed8, Format::Abgr1555, Format::Rgba8888] {
+		let (block, expected) = first_block(format);
+
+		assert_eq!(block, expected, "{:?}", format);
+	}
+}
@@ -0,0 +1,68 @@
+use tim2::{Frame, Image};
+
+fn load(path: &str) -> Image {
+	tim2::load(path).unwrap()
+}
+
+fn rebuild(template: &Frame, raw: &[u8]) -> tim2::Result<Frame> {
+	let (width, height) = (template.header().width(), template.header().height());
+
+	Frame::from_template(template, width, height, raw, Some(tim2::COLOR_KEY))
+}
+
+fn to_buffer(frame: Frame) -> Vec<u8> {
+	tim2::to_buffer(&Image::new(vec![frame])).unwrap()
+}
+
+#[test]
+fn unedited_image_writes_the_template_back() {
+	let image = load("../assets/test.tm2");
+	let template = image.get_frame(0);
+	let frame = rebuild(template, &template.to_raw(Some(tim2::COLOR_KEY))).unwrap();
+
+	assert_eq!(to_buffer(frame), tim2::to_buffer(&image).unwrap());
+}
+
+#[test]
+fn new_colors_take_unused_entries() {
+	let image = load("tests/corpus/indexed8_clut32_csm1.tm2");
+	let template = image.get_frame(0);
+	let mut raw = template.to_raw(None);
+
+	raw[0..4].copy_from_slice(&[1, 2, 3, 255]);
+
+	let frame = rebuild(template, &raw).unwrap();
+	let diff = tim2::compare(template, &frame);
+
+	assert!(diff.header.is_empty());
+	assert!(!diff.user_data_differs);
+	assert_eq!(diff.palettes.len(), 1);
+	assert_eq!(frame.to_raw(None), raw);
+}
+
+#[test]
+fn true_color_templates_keep_their_registers() {
+	let image = load("tests/corpus/abgr1555.tm2");
+	let template = image.get_frame(0);
+	let raw = vec![0xFF; template.to_raw(None).len()];
+	let frame = rebuild(template, &raw).unwrap();
+
+	assert_eq!(frame.header().gs_tex_0(), template.header().gs_tex_0());
+	assert_eq!(frame.header().storage(), template.header().storage());
+	assert_eq!(frame.to_raw(None), raw);
+}
+
+#[test]
+fn rejects_images_that_do_not_fit() {
+	let image = load("tests/corpus/indexed4_clut32.tm2");
+	let template = image.get_frame(0);
+	let (width, height) = (template.header().width(), template.header().height());
+	let raw: Vec<u8> = (0..width as usize * height as usize).flat_map(|i| [i as u8, 0, 0, 255]).collect();
+	let small = vec![0; (width as usize - 1) * height as usize * 4];
+
+	assert!(matches!(rebuild(template, &raw), Err(tim2::Error::TooManyColors(_))));
+	assert!(matches!(
+		Frame::from_template(template, width - 1, height, &small, None),
+		Err(tim2::Error::InvalidDimensions(..))
+	));
+}
@@ -0,0 +1,80 @@
+use tim2::UserData;
+
+/// Inserts a user data area into a single frame file from the corpus.
+fn with_user_data(area: &[u8]) -> Vec<u8> {
+	let mut buffer = std::fs::read("tests/corpus/rgb888.tm2").unwrap();
+	let total_size = u32::from_le_bytes([buffer[16], buffer[17], buffer[18], buffer[19]]);
+	let header_size = u16::from_le_bytes([buffer[28], buffer[29]]);
+
+	buffer[16..20].copy_from_slice(&(total_size + area.len() as u32).to_le_bytes());
+	buffer[28..30].copy_from_slice(&(header_size + area.len() as u16).to_le_bytes());
+	buffer.splice(64..64, area.iter().copied());
+	buffer
+}
+
+#[test]
+fn reads_and_writes_extended_header() {
+	let mut area = b"eXt\0\x20\0\0\0\x04\0\0\0\0\0\0\0".to_vec();
+
+	area.extend_from_slice(b"\x01\x02\x03\x04cecil\0\0\0\0\0\0\0");
+
+	let buffer = with_user_data(&area);
+	let image = tim2::from_buffer(&buffer).unwrap();
+	let user_data = image.get_frame(0).header().user_data();
+
+	assert_eq!(user_data, &UserData::Extended {
+		comment: "cecil".to_string(),
+		data: vec![1, 2, 3, 4],
+		reserved: 0,
+	});
+
+	assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+}
+
+#[test]
+fn keeps_unknown_user_data_raw() {
+	let area = [0xAAu8; 16];
+	let buffer = with_user_data(&area);
+	let image = tim2::from_buffer(&buffer).unwrap();
+
+	assert_eq!(image.get_frame(0).header().user_data(), &UserData::Raw(area.to_vec()));
+	assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+}
+
+#[test]
+fn set_user_data_resizes_the_header() {
+	let buffer = std::fs::read("tests/corpus/rgb888.tm2").unwrap();
+	let mut image = tim2::from_buffer(&buffer).unwrap();
+	let pixels = image.get_frame(0).get_pixels();
+	let user_data = UserData::Extended {
+		comment: "a comment longer than the sixteen bytes of padding".to_string(),
+		data: vec![1, 2, 3, 4, 5],
+		reserved: 7,
+	};
+
+	image.get_frame_mut(0).set_user_data(user_data.clone()).unwrap();
+
+	let header = image.get_frame(0).header();
+
+	assert_eq!(header.header_size(), 48 + 80);
+	assert_eq!(header.total_size() as usize, buffer.len() - 16 + 80);
+
+	let result = tim2::to_buffer(&image).unwrap();
+	let read = tim2::from_buffer(&result).unwrap();
+
+	assert!(tim2::validate(&result).is_empty());
+	assert_eq!(read.get_frame(0).header().user_data(), &user_data);
+	assert_eq!(read.get_frame(0).get_pixels(), pixels);
+
+	image.get_frame_mut(0).set_user_data(UserData::default()).unwrap();
+	assert_eq!(tim2::to_buffer(&image).unwrap(), buffer);
+}
+
+#[test]
+fn rejects_user_data_too_large_for_the_header() {
+	let mut image = tim2::load("tests/corpus/rgb888.tm2").unwrap();
+	let result = image.get_frame_mut(0).set_user_data(UserData::Raw(vec![0; 0x10000]));
+
+	assert!(matches!(result, Err(tim2::Error::UserDataTooLarge(0x10000, 0xFFC0))));
+	assert_eq!(image.get_frame(0).header().header_size(), 48);
+}
@@ -0,0 +1,57 @@
+use tim2::{DiagnosticKind, Severity};
+
+fn corpus(name: &str) -> Vec<u8> {
+	std::fs::read(format!("tests/corpus/{}.tm2", name)).unwrap()
+}
+
+#[test]
+fn sound_files_have_no_diagnostics() {
+	for name in &["indexed4_clut32", "indexed8_clut16_csm1", "abgr1555", "rgb888", "rgba8888"] {
+		assert_eq!(tim2::validate(&corpus(name)), vec![], "{}", name);
+	}
+}
+
+#[test]
+fn monster_sprites_leave_the_header_out_of_total_size() {
+	let buffer = std::fs::read("../../unpacker/assets/ms_000.tm2").unwrap();
+	let kinds: Vec<_> = tim2::validate(&buffer).into_iter().map(|v| v.kind).collect();
+
+	assert_eq!(kinds, vec![DiagnosticKind::TotalSizeMismatch { declared: 5120, expected: 5168 }]);
+}
+
+#[test]
+fn reports_truncated_frames() {
+	let mut buffer = corpus("rgba8888");
+
+	buffer.truncate(buffer.len() - 10);
+
+	let kinds: Vec<_> = tim2::validate(&buffer).into_iter().map(|v| v.kind).collect();
+
+	assert_eq!(kinds, vec![
+		DiagnosticKind::UnexpectedEof { needed: 10 },
+		DiagnosticKind::FrameCountMismatch { declared: 1, found: 0 },
+	]);
+}
+
+#[test]
+fn reports_every_broken_invariant() {
+	let mut buffer = corpus("indexed4_clut32");
+
+	buffer[16] ^= 0x01; // total size
+	buffer[30] = 17; // clut color count
+	buffer.extend_from_slice(&[0; 4]);
+
+	let diagnostics = tim2::validate(&buffer);
+	let kinds: Vec<_> = diagnostics.iter().map(|v| v.kind.clone()).collect();
+
+	assert_eq!(kinds, vec![
+		DiagnosticKind::TotalSizeMismatch { declared: 625, expected: 624 },
+		DiagnosticKind::TooManyClutColors { count: 17, bpp: 4 },
+		DiagnosticKind::ClutSizeMismatch { clut_size: 64, palette_size: 68 },
+		DiagnosticKind::TrailingData(3),
+	]);
+
+	assert_eq!(diagnostics[0].severity, Severity::Warning);
+	assert_eq!(diagnostics[1].frame, Some(0));
+	assert_eq!(diagnostics[3].frame, None);
+}
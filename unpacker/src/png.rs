@@ -1,10 +1,8 @@
 use crate::error::Result;
 
-use image::ColorType;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str;
-use tim2::Pixel;
 use walkdir::WalkDir;
 
 static PATH_DECODED: &str = "../iso/decoded";
@@ -22,20 +20,22 @@ fn recreate_dir(path: &str) -> Result<()> {
 }
 
 fn write_png(path: &Path, buffer: &[u8]) -> Result<()> {
-	let color_key = Pixel::from(0, 255, 0, 255);
 	let img = tim2::from_buffer(&buffer)?;
 	let frame = img.get_frame(0);
+	let stem = path.file_stem().unwrap().to_str().unwrap();
+	let mut level = 0;
 
-
-	if !frame.header().has_mipmaps() {
-		let width = frame.header().width() as u32;
-		let height = frame.header().height() as u32;
-		let raw_pixels = frame.to_raw(Some(color_key));
+	while let Some(image) = frame.mip_level_to_rgba_image(level, Some(tim2::COLOR_KEY)) {
 		let mut output_path = PathBuf::from(path);
 
+		if level > 0 {
+			output_path.set_file_name(format!("{}_mip{}", stem, level));
+		}
+
 		output_path.set_extension("png");
 
-		image::save_buffer(&output_path, &raw_pixels, width, height, ColorType::Rgba8)?;
+		image.save(&output_path)?;
+		level += 1;
 	}
 
 	Ok(())
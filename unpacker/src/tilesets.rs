@@ -2,50 +2,26 @@ use crate::error::Result;
 
 use crypto::digest::Digest;
 use crypto::sha2::Sha256;
-use image;
-use image::ColorType;
+use image::{imageops, RgbaImage};
 use std::collections::HashMap;
 use std::fs;
 use std::fs::ReadDir;
 use std::path::{Path, PathBuf};
 use tim2;
-use tim2::Pixel;
 
-const TILE_SIZE: usize = 32;
-const SECTION_SIZE: usize = TILE_SIZE * TILE_SIZE * 4;
+const TILE_SIZE: u32 = 32;
 
-type SubSection = [u8; SECTION_SIZE];
-
-struct Image {
-    width: usize,
-    height: usize,
-    raw: Vec<u8>,
-}
-
-fn get_sub_section(x_tile: usize, y_tile: usize, width: usize, buffer: &[u8]) -> SubSection {
+fn get_sub_section(x_tile: u32, y_tile: u32, image: &RgbaImage) -> RgbaImage {
     let x_start = x_tile * TILE_SIZE;
     let y_start = y_tile * TILE_SIZE;
-    let mut result = [0; SECTION_SIZE];
-
-    for y in 0..TILE_SIZE {
-        for x in 0..TILE_SIZE {
-            let local_index = ((y * TILE_SIZE) + x) * 4;
-            let image_index = (((y + y_start) * width) + (x + x_start)) * 4;
-
-            result[local_index + 0] = buffer[image_index + 0];
-            result[local_index + 1] = buffer[image_index + 1];
-            result[local_index + 2] = buffer[image_index + 2];
-            result[local_index + 3] = buffer[image_index + 3];
-        }
-    }
 
-    result
+    imageops::crop_imm(image, x_start, y_start, TILE_SIZE, TILE_SIZE).to_image()
 }
 
-fn calc_hash(buffer: &SubSection) -> String {
+fn calc_hash(buffer: &RgbaImage) -> String {
     let mut hasher = Sha256::new();
 
-    hasher.input(buffer);
+    hasher.input(buffer.as_raw());
     hasher.result_str()
 }
 
@@ -76,9 +52,8 @@ fn filter_files<P: AsRef<Path>>(path: P, name: &str, items: ReadDir) -> Vec<Path
     result
 }
 
-fn build_tile_map(name: &str, directories: &Vec<PathBuf>) -> Result<HashMap<String, SubSection>> {
+fn build_tile_map(name: &str, directories: &Vec<PathBuf>) -> Result<HashMap<String, RgbaImage>> {
     let mut tiles = HashMap::new();
-    let color_key = Pixel::from(0, 255, 0, 255);
 
     for dir in directories {
         let items = fs::read_dir(dir)?;
@@ -88,17 +63,15 @@ fn build_tile_map(name: &str, directories: &Vec<PathBuf>) -> Result<HashMap<Stri
             let image = tim2::load(file_path)?;
 
             for frame in image.frames() {
-                let width = frame.header().width() as usize;
-                let height = frame.header().height() as usize;
-                let raw = frame.to_raw(Some(color_key));
+                let image = frame.to_rgba_image(Some(tim2::COLOR_KEY));
 
-                for y in 0..(height / TILE_SIZE) {
-                    for x in 0..(width / TILE_SIZE) {
-                        let section_buffer = get_sub_section(x, y, width, &raw);
-                        let hash = calc_hash(&section_buffer);
+                for y in 0..(image.height() / TILE_SIZE) {
+                    for x in 0..(image.width() / TILE_SIZE) {
+                        let section = get_sub_section(x, y, &image);
+                        let hash = calc_hash(&section);
 
                         if !tiles.contains_key(&hash) {
-                            tiles.insert(hash, section_buffer);
+                            tiles.insert(hash, section);
                         }
                     }
                 }
@@ -109,37 +82,21 @@ fn build_tile_map(name: &str, directories: &Vec<PathBuf>) -> Result<HashMap<Stri
     Ok(tiles)
 }
 
-fn build_image(tiles: &HashMap<String, SubSection>) -> Image {
-    let width = (tiles.len() as f32).sqrt().ceil() as usize;
-    let height = (tiles.len() as f32 / width as f32).ceil() as usize;
-    let pixel_width = width * TILE_SIZE;
-    let mut raw = vec![0; width * height * TILE_SIZE * TILE_SIZE * 4];
+fn build_image(tiles: &HashMap<String, RgbaImage>) -> RgbaImage {
+    let width = (tiles.len() as f32).sqrt().ceil() as u32;
+    let height = (tiles.len() as f32 / width as f32).ceil() as u32;
+    let mut result = RgbaImage::new(width * TILE_SIZE, height * TILE_SIZE);
 
-    for (i, kvp) in tiles.iter().enumerate() {
-        let x_start = (i % width) * TILE_SIZE;
-        let y_start = (i / width) * TILE_SIZE;
-        let buffer = kvp.1;
+    for (i, tile) in tiles.values().enumerate() {
+        let x_start = (i as u32 % width) * TILE_SIZE;
+        let y_start = (i as u32 / width) * TILE_SIZE;
 
         println!("starts<{}  {}>", x_start, y_start);
 
-        for y in 0..TILE_SIZE {
-            for x in 0..TILE_SIZE {
-                let local_index = ((y * TILE_SIZE) + x) * 4;
-                let image_index = (((y + y_start) * pixel_width) + (x + x_start)) * 4;
-
-                raw[image_index + 0] = buffer[local_index + 0];
-                raw[image_index + 1] = buffer[local_index + 1];
-                raw[image_index + 2] = buffer[local_index + 2];
-                raw[image_index + 3] = buffer[local_index + 3];
-            }
-        }
+        imageops::replace(&mut result, tile, x_start as i64, y_start as i64);
     }
 
-    Image {
-        width: width * TILE_SIZE,
-        height: height * TILE_SIZE,
-        raw,
-    }
+    result
 }
 
 pub fn process<P: AsRef<Path>>(input_path: P, output_path: P, name: &str) -> Result<()> {
@@ -158,13 +115,7 @@ pub fn process<P: AsRef<Path>>(input_path: P, output_path: P, name: &str) -> Res
 
     println!("hashed {} tiles", tiles.len());
 
-    image::save_buffer(
-        &output_path,
-        &image.raw,
-        image.width as u32,
-        image.height as u32,
-        ColorType::Rgba8
-    ).unwrap();
+    image.save(&output_path)?;
 
     Ok(())
 }